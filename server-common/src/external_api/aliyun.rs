@@ -1,23 +1,26 @@
 pub mod oss {
-    use std::{collections::HashMap, str::FromStr};
+    use std::{collections::HashMap, pin::pin, str::FromStr};
 
-    use anyhow::{bail, Result};
-    use bytes::Bytes;
+    use anyhow::{anyhow, bail, Result};
+    use bytes::{Bytes, BytesMut};
     use chrono::{DateTime, Duration, Utc};
     use const_format::concatcp;
-    use futures::Stream;
+    use futures::{Stream, TryStreamExt};
     use itertools::Itertools;
     use reqwest::{
-        header::{HeaderName, HeaderValue},
-        Client as HttpClient,
+        header::{HeaderMap, HeaderName, HeaderValue},
+        Body, Client as HttpClient,
     };
     use secrecy::{ExposeSecret, SecretString};
-    use tracing::info;
+    use tracing::{info, warn};
     use uuid::Uuid;
 
     pub const RUSTWEB_PREFIX: &str = "/rust-web/";
     pub const UPLOAD_PREFIX: &str = concatcp!(RUSTWEB_PREFIX, "upload/");
 
+    // oss rejects parts smaller than 100KB, except for the last one
+    pub const MULTIPART_MIN_PART_SIZE: usize = 100 * 1024;
+
     const ENV_BUCKET_HOST: &str = "RUSTWEB_ALIYUN_OSS_BUCKET_HOST";
     const ENV_BUCKET_NAME: &str = "RUSTWEB_ALIYUN_OSS_BUCKET_NAME";
     const ENV_BUCKET_REGION: &str = "RUSTWEB_ALIYUN_OSS_BUCKET_REGION";
@@ -38,6 +41,11 @@ pub mod oss {
         http_client: &'http HttpClient,
     }
 
+    pub struct MultipartPart {
+        pub part_number: u32,
+        pub etag: String,
+    }
+
     pub struct SignResult {
         pub signature: String,
         pub extra_params: HashMap<String, String>,
@@ -198,7 +206,13 @@ pub mod oss {
                 let bucket_name = &self.bucket_name;
                 let query_string = query
                     .sorted_by(|(k1, _), (k2, _)| k1.cmp(k2))
-                    .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+                    .map(|(k, v)| {
+                        if v.is_empty() {
+                            urlencoding::encode(k).into_owned()
+                        } else {
+                            format!("{}={}", urlencoding::encode(k), urlencoding::encode(v))
+                        }
+                    })
                     .join("&");
                 let header_string = header
                     .sorted_by(|(k1, _), (k2, _)| k1.cmp(k2))
@@ -309,13 +323,318 @@ pub mod oss {
             &self,
             uri: &str,
         ) -> Result<impl Stream<Item = Result<Bytes, reqwest::Error>>> {
+            let (_, stream) = self.get_object_with_length(uri).await?;
+            Ok(stream)
+        }
+
+        pub async fn get_object_with_length(
+            &self,
+            uri: &str,
+        ) -> Result<(
+            Option<u64>,
+            impl Stream<Item = Result<Bytes, reqwest::Error>>,
+        )> {
             let sign = self.oss_client.download_url(uri, Duration::seconds(30));
             let response = self.http_client.get(sign).send().await?;
             if !response.status().is_success() {
                 bail!("get object failed: {}", response.status())
             }
 
-            Ok(response.bytes_stream())
+            Ok((response.content_length(), response.bytes_stream()))
+        }
+
+        pub async fn put_object<S, E>(
+            &self,
+            uri: &str,
+            content_type: &str,
+            content_length: u64,
+            body: S,
+        ) -> Result<()>
+        where
+            S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+            E: std::error::Error + Send + Sync + 'static,
+        {
+            let header = [("content-type", content_type)];
+
+            let sign = self.oss_client.sign_header(
+                "PUT",
+                uri,
+                [].into_iter(),
+                header.iter().copied(),
+                [].into_iter(),
+            );
+
+            let response = self
+                .http_client
+                .put(self.oss_client.path_url(uri))
+                .headers(Self::signed_headers(&header, &sign)?)
+                .header("Authorization", sign.signature)
+                // streaming bodies are sent chunked unless the length is given
+                .header("content-length", content_length)
+                .body(Body::wrap_stream(body))
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!(
+                    "put object failed: {}, {}",
+                    response.status(),
+                    response.text().await?
+                )
+            }
+            Ok(())
+        }
+
+        // upload a stream of unknown length in parts, the upload is aborted on failure
+        pub async fn put_object_multipart<S, E>(
+            &self,
+            uri: &str,
+            content_type: &str,
+            body: S,
+            part_size: usize,
+        ) -> Result<()>
+        where
+            S: Stream<Item = Result<Bytes, E>> + Send,
+            E: std::error::Error + Send + Sync + 'static,
+        {
+            if part_size < MULTIPART_MIN_PART_SIZE {
+                bail!("part size is too small: {part_size}");
+            }
+
+            let upload_id = self.initiate_multipart_upload(uri, content_type).await?;
+            let result = async {
+                let parts = self.upload_parts(uri, &upload_id, body, part_size).await?;
+                self.complete_multipart_upload(uri, &upload_id, &parts)
+                    .await
+            }
+            .await;
+
+            if result.is_err() {
+                if let Err(e) = self.abort_multipart_upload(uri, &upload_id).await {
+                    warn!("abort multipart upload failed: {uri}, {e}");
+                }
+            }
+            result
+        }
+
+        pub async fn initiate_multipart_upload(
+            &self,
+            uri: &str,
+            content_type: &str,
+        ) -> Result<String> {
+            let query = [("uploads", "")];
+            let header = [("content-type", content_type)];
+
+            let sign = self.oss_client.sign_header(
+                "POST",
+                uri,
+                query.iter().copied(),
+                header.iter().copied(),
+                [].into_iter(),
+            );
+
+            let response = self
+                .http_client
+                .post(format!("{}?uploads", self.oss_client.path_url(uri)))
+                .headers(Self::signed_headers(&header, &sign)?)
+                .header("Authorization", sign.signature)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!(
+                    "initiate multipart upload failed: {}, {}",
+                    response.status(),
+                    response.text().await?
+                )
+            }
+
+            let body = response.text().await?;
+            match xml_value(&body, "UploadId") {
+                Some(upload_id) => Ok(upload_id.to_owned()),
+                None => bail!("upload id not found in response: {body}"),
+            }
+        }
+
+        pub async fn upload_part(
+            &self,
+            uri: &str,
+            upload_id: &str,
+            part_number: u32,
+            body: Bytes,
+        ) -> Result<MultipartPart> {
+            let part_number_string = part_number.to_string();
+            let query = [
+                ("partNumber", &part_number_string as &str),
+                ("uploadId", upload_id),
+            ];
+
+            let sign = self.oss_client.sign_header(
+                "PUT",
+                uri,
+                query.iter().copied(),
+                [].into_iter(),
+                [].into_iter(),
+            );
+
+            let response = self
+                .http_client
+                .put(format!(
+                    "{}?{}",
+                    self.oss_client.path_url(uri),
+                    query_string(&query)
+                ))
+                .headers(Self::signed_headers(&[], &sign)?)
+                .header("Authorization", sign.signature)
+                .body(body)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!(
+                    "upload part {part_number} failed: {}, {}",
+                    response.status(),
+                    response.text().await?
+                )
+            }
+
+            let etag = response
+                .headers()
+                .get("etag")
+                .and_then(|etag| etag.to_str().ok())
+                .ok_or(anyhow!("etag not found in upload part response"))?;
+
+            Ok(MultipartPart {
+                part_number,
+                etag: etag.to_owned(),
+            })
+        }
+
+        pub async fn complete_multipart_upload(
+            &self,
+            uri: &str,
+            upload_id: &str,
+            parts: &[MultipartPart],
+        ) -> Result<()> {
+            let query = [("uploadId", upload_id)];
+            let body = format!(
+                "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+                parts
+                    .iter()
+                    .map(|part| format!(
+                        "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                        part.part_number, part.etag
+                    ))
+                    .join("")
+            );
+
+            let sign = self.oss_client.sign_header(
+                "POST",
+                uri,
+                query.iter().copied(),
+                [].into_iter(),
+                [].into_iter(),
+            );
+
+            let response = self
+                .http_client
+                .post(format!(
+                    "{}?{}",
+                    self.oss_client.path_url(uri),
+                    query_string(&query)
+                ))
+                .headers(Self::signed_headers(&[], &sign)?)
+                .header("Authorization", sign.signature)
+                .body(body)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!(
+                    "complete multipart upload failed: {}, {}",
+                    response.status(),
+                    response.text().await?
+                )
+            }
+            Ok(())
+        }
+
+        pub async fn abort_multipart_upload(&self, uri: &str, upload_id: &str) -> Result<()> {
+            let query = [("uploadId", upload_id)];
+
+            let sign = self.oss_client.sign_header(
+                "DELETE",
+                uri,
+                query.iter().copied(),
+                [].into_iter(),
+                [].into_iter(),
+            );
+
+            let response = self
+                .http_client
+                .delete(format!(
+                    "{}?{}",
+                    self.oss_client.path_url(uri),
+                    query_string(&query)
+                ))
+                .headers(Self::signed_headers(&[], &sign)?)
+                .header("Authorization", sign.signature)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                bail!("abort multipart upload failed: {}", response.status())
+            }
+            Ok(())
+        }
+
+        async fn upload_parts<S, E>(
+            &self,
+            uri: &str,
+            upload_id: &str,
+            body: S,
+            part_size: usize,
+        ) -> Result<Vec<MultipartPart>>
+        where
+            S: Stream<Item = Result<Bytes, E>> + Send,
+            E: std::error::Error + Send + Sync + 'static,
+        {
+            let mut body = pin!(body);
+            let mut parts = Vec::new();
+            let mut buffer = BytesMut::with_capacity(part_size);
+
+            while let Some(chunk) = body.try_next().await? {
+                buffer.extend_from_slice(&chunk);
+                while buffer.len() >= part_size {
+                    let part = buffer.split_to(part_size).freeze();
+                    let part_number = parts.len() as u32 + 1;
+                    parts.push(self.upload_part(uri, upload_id, part_number, part).await?);
+                }
+            }
+
+            // the last part may be smaller than part size, and an empty object still needs one part
+            if !buffer.is_empty() || parts.is_empty() {
+                let part_number = parts.len() as u32 + 1;
+                parts.push(
+                    self.upload_part(uri, upload_id, part_number, buffer.freeze())
+                        .await?,
+                );
+            }
+
+            Ok(parts)
+        }
+
+        fn signed_headers(header: &[(&str, &str)], sign: &SignResult) -> Result<HeaderMap> {
+            header
+                .iter()
+                .copied()
+                .chain(
+                    sign.extra_params
+                        .iter()
+                        .map(|(k, v)| (k.as_ref(), v.as_ref())),
+                )
+                .map(|(k, v)| Ok((HeaderName::from_str(k)?, HeaderValue::from_str(v)?)))
+                .collect()
         }
 
         pub async fn copy_object(&self, src: &str, dst: &str) -> Result<()> {
@@ -388,4 +707,18 @@ pub mod oss {
             Ok(())
         }
     }
+
+    fn query_string(query: &[(&str, &str)]) -> String {
+        query
+            .iter()
+            .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+            .join("&")
+    }
+
+    fn xml_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+        let start_tag = format!("<{tag}>");
+        let start = xml.find(&start_tag)? + start_tag.len();
+        let end = start + xml[start..].find(&format!("</{tag}>"))?;
+        Some(&xml[start..end])
+    }
 }
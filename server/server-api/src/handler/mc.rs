@@ -1,6 +1,17 @@
-use axum::{extract::Query, http::StatusCode, response::Redirect, Extension};
+use std::collections::HashMap;
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive},
+        Redirect, Sse,
+    },
+    Extension,
+};
 use chrono::Duration;
 use common::tonic_idl_gen::McVersionType;
+use futures::{Stream, StreamExt};
 use server_common::{external_api::aliyun::oss::OssClient, rpc_client::McServiceClient};
 
 use crate::{
@@ -10,10 +21,20 @@ use crate::{
         response::BodyResponse,
     },
     model::mc::{
-        CreateServerConfigRequest, GetCurrentServerConfigResponse, GetResourcePackRequest,
-        ListMcVersionRequest, ListMcVersionResponse, ListServerConfigRequest,
-        ListServerConfigResponse, McVersion, RunningServerStage, RunningServerStageInfo,
-        ServerConfig, StartServerConfigRequest,
+        AccessList, AddServerConfigAccessRequest, AddServerConfigAddonRequest,
+        CreateServerConfigRequest, DeleteServerConfigAccessRequest, DeleteServerConfigAddonRequest,
+        Difficulty, GameMode, GetResourcePackRequest, JvmOptions, ListMcVersionRequest,
+        ListMcVersionResponse, ListOnlinePlayersRequest, ListOnlinePlayersResponse,
+        ListPlayerSessionsRequest, ListPlayerSessionsResponse, ListRunningServersResponse,
+        ListServerConfigAccessRequest, ListServerConfigAccessResponse,
+        ListServerConfigAddonRequest, ListServerConfigAddonResponse, ListServerConfigRequest,
+        ListServerConfigResponse, ListServerRunsRequest, ListServerRunsResponse, McVersion,
+        OnlinePlayer, PlayerSession, RestartPolicy, RunningServer, RunningServerStage,
+        RunningServerStageInfo, SendServerCommandRequest, SendServerCommandResponse, ServerConfig,
+        ServerConfigAccess, ServerConfigAddon, ServerConsoleLine, ServerFlavor, ServerListStatus,
+        ServerPlayerSample, ServerProperties, ServerRun, ServerRunResult, ServerRunStageInfo,
+        StartServerConfigRequest, StopServerConfigRequest, StreamServerConsoleRequest,
+        UpdateServerConfigRequest,
     },
 };
 
@@ -69,6 +90,12 @@ pub async fn create_server_config(
         return Err(AppError::BadRequest("name too long"));
     }
 
+    if req.restart_max_retries > 100 {
+        return Err(AppError::BadRequest("restart_max_retries too large"));
+    }
+
+    validate_server_properties(&req.properties)?;
+
     if req
         .world_uri
         .as_ref()
@@ -78,7 +105,7 @@ pub async fn create_server_config(
     }
 
     if req
-        .world_uri
+        .resource_uri
         .as_ref()
         .is_some_and(|uri| !validate_upload_oss_uri(uri))
     {
@@ -89,15 +116,107 @@ pub async fn create_server_config(
         .create_server_config(common::tonic_idl_gen::CreateServerConfigRequest {
             name: req.name,
             version: req.version,
+            flavor: common::tonic_idl_gen::ServerFlavor::from(req.flavor) as i32,
+            world_uri: req.world_uri,
+            resource_uri: req.resource_uri,
+            motd: req.motd,
+            restart_policy: common::tonic_idl_gen::RestartPolicy::from(req.restart_policy) as i32,
+            restart_max_retries: req.restart_max_retries,
+            properties: Some(req.properties.into()),
+            jvm_options: Some(req.jvm_options.into()),
+        })
+        .await?;
+
+    Ok(BodyResponse::new(()))
+}
+
+#[axum::debug_handler]
+pub async fn update_server_config(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptBodyRequest(req): EncryptBodyRequest<UpdateServerConfigRequest>,
+) -> Result<BodyResponse<()>, AppError> {
+    if req.name.as_ref().is_some_and(|name| name.is_empty()) {
+        return Err(AppError::BadRequest("invalid name"));
+    }
+
+    if req.name.as_ref().is_some_and(|name| name.len() > 100) {
+        return Err(AppError::BadRequest("name too long"));
+    }
+
+    if req.restart_max_retries.is_some_and(|v| v > 100) {
+        return Err(AppError::BadRequest("restart_max_retries too large"));
+    }
+
+    if let Some(properties) = &req.properties {
+        validate_server_properties(properties)?;
+    }
+
+    if req
+        .world_uri
+        .as_ref()
+        .is_some_and(|uri| !validate_upload_oss_uri(uri))
+    {
+        return Err(AppError::BadRequest("invalid world_uri"));
+    }
+
+    if req
+        .resource_uri
+        .as_ref()
+        .is_some_and(|uri| !validate_upload_oss_uri(uri))
+    {
+        return Err(AppError::BadRequest("invalid resource_uri"));
+    }
+
+    mc_client
+        .update_server_config(common::tonic_idl_gen::UpdateServerConfigRequest {
+            id: req.id,
+            name: req.name,
+            version: req.version,
+            flavor: req
+                .flavor
+                .map(|v| common::tonic_idl_gen::ServerFlavor::from(v) as i32),
             world_uri: req.world_uri,
             resource_uri: req.resource_uri,
             motd: req.motd,
+            restart_policy: req
+                .restart_policy
+                .map(|v| common::tonic_idl_gen::RestartPolicy::from(v) as i32),
+            restart_max_retries: req.restart_max_retries,
+            properties: req.properties.map(Into::into),
+            jvm_options: req.jvm_options.map(Into::into),
         })
         .await?;
 
     Ok(BodyResponse::new(()))
 }
 
+fn validate_server_properties(properties: &ServerProperties) -> Result<(), AppError> {
+    if properties
+        .max_players
+        .is_some_and(|v| !(1..=1000).contains(&v))
+    {
+        return Err(AppError::BadRequest("invalid max_players"));
+    }
+
+    if properties
+        .view_distance
+        .is_some_and(|v| !(3..=32).contains(&v))
+    {
+        return Err(AppError::BadRequest("invalid view_distance"));
+    }
+
+    if properties
+        .level_seed
+        .as_ref()
+        .is_some_and(|seed| seed.len() > 64 || seed.contains(['\r', '\n']))
+    {
+        return Err(AppError::BadRequest("invalid level_seed"));
+    }
+
+    Ok(())
+}
+
+// 内存上限由server-mc在启动时校验
 #[axum::debug_handler]
 pub async fn list_server_config(
     Extension(mut mc_client): Extension<McServiceClient>,
@@ -124,12 +243,7 @@ pub async fn list_server_config(
         configs: list_configs
             .configs
             .into_iter()
-            .map(|config| ServerConfig {
-                id: config.id,
-                name: config.name,
-                version: config.version,
-                motd: config.motd,
-            })
+            .map(ServerConfig::from)
             .collect(),
     }))
 }
@@ -149,132 +263,747 @@ pub async fn start_server_config(
 #[axum::debug_handler]
 pub async fn stop_server_config(
     Extension(mut mc_client): Extension<McServiceClient>,
-    EncryptBodyRequest(_req): EncryptBodyRequest<()>,
+    EncryptBodyRequest(req): EncryptBodyRequest<StopServerConfigRequest>,
 ) -> Result<BodyResponse<()>, AppError> {
     mc_client
-        .stop_server_config(common::tonic_idl_gen::StopServerConfigRequest {})
+        .stop_server_config(common::tonic_idl_gen::StopServerConfigRequest { id: req.id })
         .await?;
 
     Ok(BodyResponse::new(()))
 }
 
 #[axum::debug_handler]
-pub async fn get_current_server_config(
+pub async fn list_running_servers(
     Extension(mut mc_client): Extension<McServiceClient>,
     EncryptQueryRequest(_req): EncryptQueryRequest<()>,
-) -> Result<BodyResponse<GetCurrentServerConfigResponse>, AppError> {
-    let current_server_config = mc_client
-        .get_current_server_config(common::tonic_idl_gen::GetCurrentServerConfigRequest {})
+) -> Result<BodyResponse<ListRunningServersResponse>, AppError> {
+    let running_servers = mc_client
+        .list_running_servers(common::tonic_idl_gen::ListRunningServersRequest {})
         .await?
         .into_inner();
 
-    Ok(BodyResponse::new(GetCurrentServerConfigResponse {
-        running_config: current_server_config
-            .running_config
-            .map(|server_config| ServerConfig {
-                id: server_config.id,
-                name: server_config.name,
-                version: server_config.version,
-                motd: server_config.motd,
-            }),
-        status: current_server_config
-            .status
-            .map(|status| {
-                status
-                    .stage_info
-                    .into_iter()
-                    .filter_map(|info| {
-                        // FIXME: extract this code and avoid repeat
-                        Some((
-                            match info.stage {
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::Init as i32 =>
-                                {
-                                    RunningServerStage::Init
-                                }
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::PullingServer
-                                        as i32 =>
-                                {
-                                    RunningServerStage::PullingServer
-                                }
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::PullingWorld
-                                        as i32 =>
-                                {
-                                    RunningServerStage::PullingWorld
-                                }
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::InitializingFile
-                                        as i32 =>
-                                {
-                                    RunningServerStage::InitializingFile
-                                }
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::Starting
-                                        as i32 =>
-                                {
-                                    RunningServerStage::Starting
-                                }
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::Running
-                                        as i32 =>
-                                {
-                                    RunningServerStage::Running
-                                }
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::Stopping
-                                        as i32 =>
-                                {
-                                    RunningServerStage::Stopping
-                                }
-                                v if v
-                                    == common::tonic_idl_gen::RunningServerStage::Stopped
-                                        as i32 =>
-                                {
-                                    RunningServerStage::Stopped
-                                }
-
-                                _ => return None,
-                            },
-                            RunningServerStageInfo {
-                                enter_time: info.enter_time,
-                                finish_time: info.finish_time,
-                                in_error: info.in_error,
-                                error_message: info.error_message,
-                            },
-                        ))
-                    })
-                    .collect()
+    Ok(BodyResponse::new(ListRunningServersResponse {
+        servers: running_servers
+            .servers
+            .into_iter()
+            .filter_map(|server| {
+                let config = server.config?;
+
+                let mut status = HashMap::new();
+                let mut crashes = Vec::new();
+                for info in server
+                    .status
+                    .map(|status| status.stage_info)
+                    .unwrap_or_default()
+                {
+                    let Ok(stage) = common::tonic_idl_gen::RunningServerStage::try_from(info.stage)
+                    else {
+                        continue;
+                    };
+                    let stage_info = RunningServerStageInfo {
+                        enter_time: info.enter_time,
+                        finish_time: info.finish_time,
+                        in_error: info.in_error,
+                        error_message: info.error_message,
+                        exit_code: info.exit_code,
+                        bytes_done: info.bytes_done,
+                        bytes_total: info.bytes_total,
+                        entries_extracted: info.entries_extracted,
+                        entries_total: info.entries_total,
+                    };
+
+                    // 每次崩溃都会单独记录，不能按阶段合并
+                    match stage.into() {
+                        RunningServerStage::Crashed => crashes.push(stage_info),
+                        stage => {
+                            status.insert(stage, stage_info);
+                        }
+                    }
+                }
+                crashes.sort_by_key(|info| info.enter_time);
+
+                Some(RunningServer {
+                    config: config.into(),
+                    port: server.port,
+                    status,
+                    crashes,
+                    list_status: server.list_status.map(Into::into),
+                })
             })
-            .unwrap_or_default(),
+            .collect(),
     }))
 }
 
+#[axum::debug_handler]
+pub async fn stream_server_console(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptQueryRequest(req): EncryptQueryRequest<StreamServerConsoleRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    let console = mc_client
+        .stream_server_console(common::tonic_idl_gen::StreamServerConsoleRequest { id: req.id })
+        .await?
+        .into_inner();
+
+    let events = console
+        .filter_map(|line| std::future::ready(line.ok()))
+        .map(|line| {
+            Event::default().json_data(ServerConsoleLine {
+                time: line.time,
+                line: line.line,
+            })
+        });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[axum::debug_handler]
+pub async fn send_server_command(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptBodyRequest(req): EncryptBodyRequest<SendServerCommandRequest>,
+) -> Result<BodyResponse<SendServerCommandResponse>, AppError> {
+    let command = req.command.trim();
+
+    if command.is_empty() {
+        return Err(AppError::BadRequest("invalid command"));
+    }
+
+    if command.len() > 256 {
+        return Err(AppError::BadRequest("command too long"));
+    }
+
+    if command.contains(['\r', '\n']) {
+        return Err(AppError::BadRequest("invalid command"));
+    }
+
+    let response = mc_client
+        .send_server_command(common::tonic_idl_gen::SendServerCommandRequest {
+            id: req.id,
+            command: command.to_owned(),
+        })
+        .await?
+        .into_inner();
+
+    Ok(BodyResponse::new(SendServerCommandResponse {
+        output: response.output,
+    }))
+}
+
+#[axum::debug_handler]
+pub async fn add_server_config_addon(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptBodyRequest(req): EncryptBodyRequest<AddServerConfigAddonRequest>,
+) -> Result<BodyResponse<ServerConfigAddon>, AppError> {
+    if !validate_addon_name(&req.name) {
+        return Err(AppError::BadRequest("invalid name"));
+    }
+
+    if !validate_upload_oss_uri(&req.uri) {
+        return Err(AppError::BadRequest("invalid uri"));
+    }
+
+    let addon = mc_client
+        .add_server_config_addon(common::tonic_idl_gen::AddServerConfigAddonRequest {
+            server_config_id: req.server_config_id,
+            name: req.name,
+            uri: req.uri,
+        })
+        .await?
+        .into_inner()
+        .addon
+        .ok_or(AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    Ok(BodyResponse::new(addon.into()))
+}
+
+#[axum::debug_handler]
+pub async fn list_server_config_addon(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptQueryRequest(req): EncryptQueryRequest<ListServerConfigAddonRequest>,
+) -> Result<BodyResponse<ListServerConfigAddonResponse>, AppError> {
+    let response = mc_client
+        .list_server_config_addon(common::tonic_idl_gen::ListServerConfigAddonRequest {
+            server_config_id: req.server_config_id,
+        })
+        .await?
+        .into_inner();
+
+    Ok(BodyResponse::new(ListServerConfigAddonResponse {
+        addons: response
+            .addons
+            .into_iter()
+            .map(ServerConfigAddon::from)
+            .collect(),
+    }))
+}
+
+#[axum::debug_handler]
+pub async fn delete_server_config_addon(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptBodyRequest(req): EncryptBodyRequest<DeleteServerConfigAddonRequest>,
+) -> Result<BodyResponse<()>, AppError> {
+    mc_client
+        .delete_server_config_addon(common::tonic_idl_gen::DeleteServerConfigAddonRequest {
+            id: req.id,
+        })
+        .await?;
+
+    Ok(BodyResponse::new(()))
+}
+
+#[axum::debug_handler]
+pub async fn list_server_runs(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptQueryRequest(req): EncryptQueryRequest<ListServerRunsRequest>,
+) -> Result<BodyResponse<ListServerRunsResponse>, AppError> {
+    if req.offset > 10000 {
+        return Err(AppError::BadRequest("Invalid request offset"));
+    }
+
+    if req.limit > 100 {
+        return Err(AppError::BadRequest("Invalid request limit"));
+    }
+
+    let response = mc_client
+        .list_server_runs(common::tonic_idl_gen::ListServerRunsRequest {
+            server_config_id: req.server_config_id,
+            offset: req.offset,
+            count: req.limit,
+        })
+        .await?
+        .into_inner();
+
+    Ok(BodyResponse::new(ListServerRunsResponse {
+        count: response.total,
+        runs: response.runs.into_iter().map(ServerRun::from).collect(),
+    }))
+}
+
+#[axum::debug_handler]
+pub async fn add_server_config_access(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptBodyRequest(req): EncryptBodyRequest<AddServerConfigAccessRequest>,
+) -> Result<BodyResponse<ServerConfigAccess>, AppError> {
+    if !validate_player_name(&req.player_name) {
+        return Err(AppError::BadRequest("invalid player name"));
+    }
+
+    if req.op_level.is_some_and(|level| !(1..=4).contains(&level)) {
+        return Err(AppError::BadRequest("invalid op level"));
+    }
+
+    if req
+        .reason
+        .as_ref()
+        .is_some_and(|reason| reason.len() > 256 || reason.contains(['\r', '\n']))
+    {
+        return Err(AppError::BadRequest("invalid reason"));
+    }
+
+    let access = mc_client
+        .add_server_config_access(common::tonic_idl_gen::AddServerConfigAccessRequest {
+            server_config_id: req.server_config_id,
+            list: common::tonic_idl_gen::AccessList::from(req.list) as i32,
+            player_name: req.player_name,
+            op_level: req.op_level,
+            reason: req.reason,
+        })
+        .await?
+        .into_inner()
+        .access
+        .ok_or(AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    Ok(BodyResponse::new(access.into()))
+}
+
+#[axum::debug_handler]
+pub async fn list_server_config_access(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptQueryRequest(req): EncryptQueryRequest<ListServerConfigAccessRequest>,
+) -> Result<BodyResponse<ListServerConfigAccessResponse>, AppError> {
+    let response = mc_client
+        .list_server_config_access(common::tonic_idl_gen::ListServerConfigAccessRequest {
+            server_config_id: req.server_config_id,
+        })
+        .await?
+        .into_inner();
+
+    Ok(BodyResponse::new(ListServerConfigAccessResponse {
+        accesses: response
+            .accesses
+            .into_iter()
+            .map(ServerConfigAccess::from)
+            .collect(),
+    }))
+}
+
+#[axum::debug_handler]
+pub async fn delete_server_config_access(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptBodyRequest(req): EncryptBodyRequest<DeleteServerConfigAccessRequest>,
+) -> Result<BodyResponse<()>, AppError> {
+    mc_client
+        .delete_server_config_access(common::tonic_idl_gen::DeleteServerConfigAccessRequest {
+            id: req.id,
+        })
+        .await?;
+
+    Ok(BodyResponse::new(()))
+}
+
+// 玩家名只能包含字母、数字和下划线
+fn validate_player_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 16
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[axum::debug_handler]
+pub async fn list_online_players(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptQueryRequest(req): EncryptQueryRequest<ListOnlinePlayersRequest>,
+) -> Result<BodyResponse<ListOnlinePlayersResponse>, AppError> {
+    let response = mc_client
+        .list_online_players(common::tonic_idl_gen::ListOnlinePlayersRequest {
+            server_config_id: req.server_config_id,
+        })
+        .await?
+        .into_inner();
+
+    Ok(BodyResponse::new(ListOnlinePlayersResponse {
+        players: response
+            .players
+            .into_iter()
+            .map(|player| OnlinePlayer {
+                name: player.name,
+                uuid: player.uuid,
+                join_time: player.join_time,
+            })
+            .collect(),
+    }))
+}
+
+#[axum::debug_handler]
+pub async fn list_player_sessions(
+    Extension(mut mc_client): Extension<McServiceClient>,
+    EncryptQueryRequest(req): EncryptQueryRequest<ListPlayerSessionsRequest>,
+) -> Result<BodyResponse<ListPlayerSessionsResponse>, AppError> {
+    if req.offset > 10000 {
+        return Err(AppError::BadRequest("Invalid request offset"));
+    }
+
+    if req.limit > 100 {
+        return Err(AppError::BadRequest("Invalid request limit"));
+    }
+
+    if req
+        .player_name
+        .as_ref()
+        .is_some_and(|name| name.is_empty() || name.len() > 16)
+    {
+        return Err(AppError::BadRequest("Invalid player name"));
+    }
+
+    let response = mc_client
+        .list_player_sessions(common::tonic_idl_gen::ListPlayerSessionsRequest {
+            server_config_id: req.server_config_id,
+            player_name: req.player_name,
+            offset: req.offset,
+            count: req.limit,
+        })
+        .await?
+        .into_inner();
+
+    Ok(BodyResponse::new(ListPlayerSessionsResponse {
+        count: response.total,
+        sessions: response
+            .sessions
+            .into_iter()
+            .map(PlayerSession::from)
+            .collect(),
+    }))
+}
+
+// 文件名会写入服务器的mods或plugins目录
+fn validate_addon_name(name: &str) -> bool {
+    name.len() <= 128
+        && !name.starts_with('.')
+        && name.ends_with(".jar")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'))
+}
+
 #[axum::debug_handler]
 pub async fn get_resource_pack(
     Extension(mut mc_client): Extension<McServiceClient>,
     Extension(oss_client): Extension<OssClient>,
     Query(req): Query<GetResourcePackRequest>,
 ) -> Result<Redirect, StatusCode> {
-    let current_server_config = mc_client
-        .get_current_server_config(common::tonic_idl_gen::GetCurrentServerConfigRequest {})
+    if req
+        .hash
+        .as_ref()
+        .is_some_and(|hash| hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let running_servers = mc_client
+        .list_running_servers(common::tonic_idl_gen::ListRunningServersRequest {})
         .await
         .map_err(|_e| StatusCode::INTERNAL_SERVER_ERROR)?
         .into_inner();
 
-    let Some(running_config) = current_server_config.running_config else {
+    let Some(running_server) = running_servers.servers.into_iter().find(|server| {
+        server
+            .config
+            .as_ref()
+            .is_some_and(|config| config.id == req.id)
+    }) else {
         return Err(StatusCode::NOT_FOUND);
     };
 
-    if running_config.id != req.id {
+    let Some(resource_uri) = running_server.config.and_then(|config| config.resource_uri) else {
         return Err(StatusCode::NOT_FOUND);
-    }
+    };
 
-    let Some(resource_uri) = running_config.resource_uri else {
+    // 资源包更新后旧地址不再可用，避免客户端按旧的sha1校验新文件
+    if req.hash.as_ref().is_some_and(|hash| {
+        !running_server
+            .resource_pack_sha1
+            .as_ref()
+            .is_some_and(|sha1| sha1.eq_ignore_ascii_case(hash))
+    }) {
         return Err(StatusCode::NOT_FOUND);
-    };
+    }
 
     let download_url = oss_client.download_url(&resource_uri, Duration::hours(1));
     Ok(Redirect::to(&download_url))
 }
+
+impl From<common::tonic_idl_gen::ServerListStatus> for ServerListStatus {
+    fn from(value: common::tonic_idl_gen::ServerListStatus) -> Self {
+        Self {
+            version: value.version,
+            online_players: value.online_players,
+            max_players: value.max_players,
+            players: value
+                .players
+                .into_iter()
+                .map(|player| ServerPlayerSample {
+                    name: player.name,
+                    id: player.id,
+                })
+                .collect(),
+            latency_ms: value.latency_ms,
+            update_time: value.update_time,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerConfigAddon> for ServerConfigAddon {
+    fn from(value: common::tonic_idl_gen::ServerConfigAddon) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            name: value.name,
+            create_time: value.create_time,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerRun> for ServerRun {
+    fn from(value: common::tonic_idl_gen::ServerRun) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            start_time: value.start_time,
+            stop_time: value.stop_time,
+            result: value.result().into(),
+            final_stage: value.final_stage.and_then(|stage| {
+                common::tonic_idl_gen::RunningServerStage::try_from(stage)
+                    .ok()
+                    .map(Into::into)
+            }),
+            error_message: value.error_message,
+            exit_code: value.exit_code,
+            stages: value
+                .stage_info
+                .into_iter()
+                .filter_map(|info| {
+                    let stage =
+                        common::tonic_idl_gen::RunningServerStage::try_from(info.stage).ok()?;
+                    Some(ServerRunStageInfo {
+                        stage: stage.into(),
+                        info: RunningServerStageInfo {
+                            enter_time: info.enter_time,
+                            finish_time: info.finish_time,
+                            in_error: info.in_error,
+                            error_message: info.error_message,
+                            exit_code: info.exit_code,
+                            bytes_done: info.bytes_done,
+                            bytes_total: info.bytes_total,
+                            entries_extracted: info.entries_extracted,
+                            entries_total: info.entries_total,
+                        },
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerRunResult> for ServerRunResult {
+    fn from(value: common::tonic_idl_gen::ServerRunResult) -> Self {
+        match value {
+            common::tonic_idl_gen::ServerRunResult::RunStopped => ServerRunResult::Stopped,
+            common::tonic_idl_gen::ServerRunResult::RunCrashed => ServerRunResult::Crashed,
+            common::tonic_idl_gen::ServerRunResult::RunFailed => ServerRunResult::Failed,
+            common::tonic_idl_gen::ServerRunResult::RunCancelled => ServerRunResult::Cancelled,
+            common::tonic_idl_gen::ServerRunResult::RunRunning => ServerRunResult::Running,
+            common::tonic_idl_gen::ServerRunResult::RunInterrupted => ServerRunResult::Interrupted,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerConfigAccess> for ServerConfigAccess {
+    fn from(value: common::tonic_idl_gen::ServerConfigAccess) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            list: value.list().into(),
+            player_name: value.player_name,
+            player_uuid: value.player_uuid,
+            op_level: value.op_level,
+            reason: value.reason,
+            create_time: value.create_time,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::AccessList> for AccessList {
+    fn from(value: common::tonic_idl_gen::AccessList) -> Self {
+        match value {
+            common::tonic_idl_gen::AccessList::Whitelist => AccessList::Whitelist,
+            common::tonic_idl_gen::AccessList::Ops => AccessList::Ops,
+            common::tonic_idl_gen::AccessList::BannedPlayers => AccessList::BannedPlayers,
+        }
+    }
+}
+
+impl From<AccessList> for common::tonic_idl_gen::AccessList {
+    fn from(value: AccessList) -> Self {
+        match value {
+            AccessList::Whitelist => common::tonic_idl_gen::AccessList::Whitelist,
+            AccessList::Ops => common::tonic_idl_gen::AccessList::Ops,
+            AccessList::BannedPlayers => common::tonic_idl_gen::AccessList::BannedPlayers,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::PlayerSession> for PlayerSession {
+    fn from(value: common::tonic_idl_gen::PlayerSession) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            player_name: value.player_name,
+            player_uuid: value.player_uuid,
+            join_time: value.join_time,
+            leave_time: value.leave_time,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::RunningServerStage> for RunningServerStage {
+    fn from(value: common::tonic_idl_gen::RunningServerStage) -> Self {
+        match value {
+            common::tonic_idl_gen::RunningServerStage::Init => RunningServerStage::Init,
+            common::tonic_idl_gen::RunningServerStage::SelectingJava => {
+                RunningServerStage::SelectingJava
+            }
+            common::tonic_idl_gen::RunningServerStage::PullingServer => {
+                RunningServerStage::PullingServer
+            }
+            common::tonic_idl_gen::RunningServerStage::PullingWorld => {
+                RunningServerStage::PullingWorld
+            }
+            common::tonic_idl_gen::RunningServerStage::InstallingAddons => {
+                RunningServerStage::InstallingAddons
+            }
+            common::tonic_idl_gen::RunningServerStage::InitializingFile => {
+                RunningServerStage::InitializingFile
+            }
+            common::tonic_idl_gen::RunningServerStage::Starting => RunningServerStage::Starting,
+            common::tonic_idl_gen::RunningServerStage::Running => RunningServerStage::Running,
+            common::tonic_idl_gen::RunningServerStage::Stopping => RunningServerStage::Stopping,
+            common::tonic_idl_gen::RunningServerStage::Cancelled => RunningServerStage::Cancelled,
+            common::tonic_idl_gen::RunningServerStage::Stopped => RunningServerStage::Stopped,
+            common::tonic_idl_gen::RunningServerStage::Crashed => RunningServerStage::Crashed,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerConfig> for ServerConfig {
+    fn from(value: common::tonic_idl_gen::ServerConfig) -> Self {
+        Self {
+            restart_policy: value.restart_policy().into(),
+            flavor: value.flavor().into(),
+            id: value.id,
+            name: value.name,
+            version: value.version,
+            motd: value.motd,
+            restart_max_retries: value.restart_max_retries,
+            properties: value.properties.map(Into::into).unwrap_or_default(),
+            jvm_options: value.jvm_options.map(Into::into).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<JvmOptions> for common::tonic_idl_gen::JvmOptions {
+    fn from(value: JvmOptions) -> Self {
+        Self {
+            min_memory_mb: value.min_memory_mb,
+            max_memory_mb: value.max_memory_mb,
+            extra_args: value.extra_args,
+            aikar_flags: value.aikar_flags,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::JvmOptions> for JvmOptions {
+    fn from(value: common::tonic_idl_gen::JvmOptions) -> Self {
+        Self {
+            min_memory_mb: value.min_memory_mb,
+            max_memory_mb: value.max_memory_mb,
+            extra_args: value.extra_args,
+            aikar_flags: value.aikar_flags,
+        }
+    }
+}
+
+impl From<ServerProperties> for common::tonic_idl_gen::ServerProperties {
+    fn from(value: ServerProperties) -> Self {
+        Self {
+            gamemode: value
+                .gamemode
+                .map(|v| common::tonic_idl_gen::GameMode::from(v) as i32),
+            difficulty: value
+                .difficulty
+                .map(|v| common::tonic_idl_gen::Difficulty::from(v) as i32),
+            max_players: value.max_players,
+            pvp: value.pvp,
+            level_seed: value.level_seed,
+            white_list: value.white_list,
+            online_mode: value.online_mode,
+            view_distance: value.view_distance,
+            enable_command_block: value.enable_command_block,
+            require_resource_pack: value.require_resource_pack,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerProperties> for ServerProperties {
+    fn from(value: common::tonic_idl_gen::ServerProperties) -> Self {
+        Self {
+            gamemode: value
+                .gamemode
+                .and_then(|v| common::tonic_idl_gen::GameMode::try_from(v).ok())
+                .map(Into::into),
+            difficulty: value
+                .difficulty
+                .and_then(|v| common::tonic_idl_gen::Difficulty::try_from(v).ok())
+                .map(Into::into),
+            max_players: value.max_players,
+            pvp: value.pvp,
+            level_seed: value.level_seed,
+            white_list: value.white_list,
+            online_mode: value.online_mode,
+            view_distance: value.view_distance,
+            enable_command_block: value.enable_command_block,
+            require_resource_pack: value.require_resource_pack,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::GameMode> for GameMode {
+    fn from(value: common::tonic_idl_gen::GameMode) -> Self {
+        match value {
+            common::tonic_idl_gen::GameMode::Survival => GameMode::Survival,
+            common::tonic_idl_gen::GameMode::Creative => GameMode::Creative,
+            common::tonic_idl_gen::GameMode::Adventure => GameMode::Adventure,
+            common::tonic_idl_gen::GameMode::Spectator => GameMode::Spectator,
+        }
+    }
+}
+
+impl From<GameMode> for common::tonic_idl_gen::GameMode {
+    fn from(value: GameMode) -> Self {
+        match value {
+            GameMode::Survival => common::tonic_idl_gen::GameMode::Survival,
+            GameMode::Creative => common::tonic_idl_gen::GameMode::Creative,
+            GameMode::Adventure => common::tonic_idl_gen::GameMode::Adventure,
+            GameMode::Spectator => common::tonic_idl_gen::GameMode::Spectator,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::Difficulty> for Difficulty {
+    fn from(value: common::tonic_idl_gen::Difficulty) -> Self {
+        match value {
+            common::tonic_idl_gen::Difficulty::Peaceful => Difficulty::Peaceful,
+            common::tonic_idl_gen::Difficulty::Easy => Difficulty::Easy,
+            common::tonic_idl_gen::Difficulty::Normal => Difficulty::Normal,
+            common::tonic_idl_gen::Difficulty::Hard => Difficulty::Hard,
+        }
+    }
+}
+
+impl From<Difficulty> for common::tonic_idl_gen::Difficulty {
+    fn from(value: Difficulty) -> Self {
+        match value {
+            Difficulty::Peaceful => common::tonic_idl_gen::Difficulty::Peaceful,
+            Difficulty::Easy => common::tonic_idl_gen::Difficulty::Easy,
+            Difficulty::Normal => common::tonic_idl_gen::Difficulty::Normal,
+            Difficulty::Hard => common::tonic_idl_gen::Difficulty::Hard,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerFlavor> for ServerFlavor {
+    fn from(value: common::tonic_idl_gen::ServerFlavor) -> Self {
+        match value {
+            common::tonic_idl_gen::ServerFlavor::Vanilla => ServerFlavor::Vanilla,
+            common::tonic_idl_gen::ServerFlavor::Paper => ServerFlavor::Paper,
+            common::tonic_idl_gen::ServerFlavor::Fabric => ServerFlavor::Fabric,
+        }
+    }
+}
+
+impl From<ServerFlavor> for common::tonic_idl_gen::ServerFlavor {
+    fn from(value: ServerFlavor) -> Self {
+        match value {
+            ServerFlavor::Vanilla => common::tonic_idl_gen::ServerFlavor::Vanilla,
+            ServerFlavor::Paper => common::tonic_idl_gen::ServerFlavor::Paper,
+            ServerFlavor::Fabric => common::tonic_idl_gen::ServerFlavor::Fabric,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::RestartPolicy> for RestartPolicy {
+    fn from(value: common::tonic_idl_gen::RestartPolicy) -> Self {
+        match value {
+            common::tonic_idl_gen::RestartPolicy::Never => RestartPolicy::Never,
+            common::tonic_idl_gen::RestartPolicy::OnFailure => RestartPolicy::OnFailure,
+            common::tonic_idl_gen::RestartPolicy::Always => RestartPolicy::Always,
+        }
+    }
+}
+
+impl From<RestartPolicy> for common::tonic_idl_gen::RestartPolicy {
+    fn from(value: RestartPolicy) -> Self {
+        match value {
+            RestartPolicy::Never => common::tonic_idl_gen::RestartPolicy::Never,
+            RestartPolicy::OnFailure => common::tonic_idl_gen::RestartPolicy::OnFailure,
+            RestartPolicy::Always => common::tonic_idl_gen::RestartPolicy::Always,
+        }
+    }
+}
@@ -32,6 +32,10 @@ async fn main() {
             "/api/mc/server_config/create",
             post(handler::mc::create_server_config),
         )
+        .route(
+            "/api/mc/server_config/update",
+            post(handler::mc::update_server_config),
+        )
         .route(
             "/api/mc/server_config/list",
             get(handler::mc::list_server_config),
@@ -45,8 +49,52 @@ async fn main() {
             post(handler::mc::stop_server_config),
         )
         .route(
-            "/api/mc/server_config/process/info",
-            get(handler::mc::get_current_server_config),
+            "/api/mc/server_config/process/list",
+            get(handler::mc::list_running_servers),
+        )
+        .route(
+            "/api/mc/server_config/process/console",
+            get(handler::mc::stream_server_console),
+        )
+        .route(
+            "/api/mc/server_config/process/command",
+            post(handler::mc::send_server_command),
+        )
+        .route(
+            "/api/mc/server_config/process/runs",
+            get(handler::mc::list_server_runs),
+        )
+        .route(
+            "/api/mc/server_config/addon/add",
+            post(handler::mc::add_server_config_addon),
+        )
+        .route(
+            "/api/mc/server_config/addon/list",
+            get(handler::mc::list_server_config_addon),
+        )
+        .route(
+            "/api/mc/server_config/addon/delete",
+            post(handler::mc::delete_server_config_addon),
+        )
+        .route(
+            "/api/mc/server_config/access/add",
+            post(handler::mc::add_server_config_access),
+        )
+        .route(
+            "/api/mc/server_config/access/list",
+            get(handler::mc::list_server_config_access),
+        )
+        .route(
+            "/api/mc/server_config/access/delete",
+            post(handler::mc::delete_server_config_access),
+        )
+        .route(
+            "/api/mc/server_config/player/online",
+            get(handler::mc::list_online_players),
+        )
+        .route(
+            "/api/mc/server_config/player/sessions",
+            get(handler::mc::list_player_sessions),
         )
         .route("/api/mc/resource-pack", get(handler::mc::get_resource_pack))
         .route("/api/oss/upload", get(handler::oss::get_upload_signature))
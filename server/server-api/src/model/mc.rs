@@ -28,9 +28,35 @@ pub struct McVersion {
 pub struct CreateServerConfigRequest {
     pub name: String,
     pub version: String,
+    #[serde(default)]
+    pub flavor: ServerFlavor,
     pub world_uri: Option<String>,
     pub resource_uri: Option<String>,
     pub motd: String,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub restart_max_retries: u32,
+    #[serde(default)]
+    pub properties: ServerProperties,
+    #[serde(default)]
+    pub jvm_options: JvmOptions,
+}
+
+// 未设置的字段保持不变
+#[derive(Debug, Deserialize)]
+pub struct UpdateServerConfigRequest {
+    pub id: u64,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub flavor: Option<ServerFlavor>,
+    pub world_uri: Option<String>,
+    pub resource_uri: Option<String>,
+    pub motd: Option<String>,
+    pub restart_policy: Option<RestartPolicy>,
+    pub restart_max_retries: Option<u32>,
+    pub properties: Option<ServerProperties>,
+    pub jvm_options: Option<JvmOptions>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,7 +78,74 @@ pub struct ServerConfig {
     pub id: u64,
     pub name: String,
     pub version: String,
+    pub flavor: ServerFlavor,
     pub motd: String,
+    pub restart_policy: RestartPolicy,
+    pub restart_max_retries: u32,
+    pub properties: ServerProperties,
+    pub jvm_options: JvmOptions,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerFlavor {
+    #[default]
+    Vanilla,
+    Paper,
+    Fabric,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+// 未设置的字段使用服务器默认值
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ServerProperties {
+    pub gamemode: Option<GameMode>,
+    pub difficulty: Option<Difficulty>,
+    pub max_players: Option<u32>,
+    pub pvp: Option<bool>,
+    pub level_seed: Option<String>,
+    pub white_list: Option<bool>,
+    pub online_mode: Option<bool>,
+    pub view_distance: Option<u32>,
+    pub enable_command_block: Option<bool>,
+    pub require_resource_pack: Option<bool>,
+}
+
+// 内存大小单位为MB
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JvmOptions {
+    pub min_memory_mb: Option<u32>,
+    pub max_memory_mb: Option<u32>,
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    #[serde(default)]
+    pub aikar_flags: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
 }
 
 #[derive(Debug, Deserialize)]
@@ -60,23 +153,57 @@ pub struct StartServerConfigRequest {
     pub id: u64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct StopServerConfigRequest {
+    pub id: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListRunningServersResponse {
+    pub servers: Vec<RunningServer>,
+}
+
 #[derive(Debug, Serialize)]
-pub struct GetCurrentServerConfigResponse {
-    pub running_config: Option<ServerConfig>,
+pub struct RunningServer {
+    pub config: ServerConfig,
+    pub port: u32,
     pub status: HashMap<RunningServerStage, RunningServerStageInfo>,
+    pub crashes: Vec<RunningServerStageInfo>,
+    pub list_status: Option<ServerListStatus>,
+}
+
+// 通过Server List Ping获取的服务器状态
+#[derive(Debug, Serialize)]
+pub struct ServerListStatus {
+    pub version: String,
+    pub online_players: u32,
+    pub max_players: u32,
+    pub players: Vec<ServerPlayerSample>,
+    pub latency_ms: u32,
+    pub update_time: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerPlayerSample {
+    pub name: String,
+    pub id: String,
 }
 
 #[derive(Debug, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum RunningServerStage {
     Init,
+    SelectingJava,
     PullingServer,
     PullingWorld,
+    InstallingAddons,
     InitializingFile,
     Starting,
     Running,
     Stopping,
+    Cancelled,
     Stopped,
+    Crashed,
 }
 
 #[derive(Debug, Serialize)]
@@ -85,9 +212,202 @@ pub struct RunningServerStageInfo {
     pub finish_time: Option<i64>,
     pub in_error: bool,
     pub error_message: Option<String>,
+    pub exit_code: Option<i32>,
+    // 下载阶段的进度，其他阶段为空
+    pub bytes_done: Option<u64>,
+    pub bytes_total: Option<u64>,
+    pub entries_extracted: Option<u64>,
+    pub entries_total: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListServerRunsRequest {
+    pub server_config_id: u64,
+    #[serde(default = "super::default_offset")]
+    pub offset: u64,
+    #[serde(default = "super::default_limit")]
+    pub limit: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListServerRunsResponse {
+    pub count: i64,
+    pub runs: Vec<ServerRun>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerRun {
+    pub id: u64,
+    pub server_config_id: u64,
+    pub start_time: i64,
+    pub stop_time: i64,
+    pub result: ServerRunResult,
+    pub final_stage: Option<RunningServerStage>,
+    pub error_message: Option<String>,
+    pub exit_code: Option<i32>,
+    pub stages: Vec<ServerRunStageInfo>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerRunResult {
+    Stopped,
+    Crashed,
+    Failed,
+    Cancelled,
+    Running,
+    Interrupted,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerRunStageInfo {
+    pub stage: RunningServerStage,
+    #[serde(flatten)]
+    pub info: RunningServerStageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamServerConsoleRequest {
+    pub id: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerConsoleLine {
+    pub time: i64,
+    pub line: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SendServerCommandRequest {
+    pub id: u64,
+    pub command: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SendServerCommandResponse {
+    pub output: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddServerConfigAddonRequest {
+    pub server_config_id: u64,
+    pub name: String,
+    pub uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListServerConfigAddonRequest {
+    pub server_config_id: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListServerConfigAddonResponse {
+    pub addons: Vec<ServerConfigAddon>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerConfigAddon {
+    pub id: u64,
+    pub server_config_id: u64,
+    pub name: String,
+    pub create_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteServerConfigAddonRequest {
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessList {
+    Whitelist,
+    Ops,
+    BannedPlayers,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddServerConfigAccessRequest {
+    pub server_config_id: u64,
+    pub list: AccessList,
+    pub player_name: String,
+    pub op_level: Option<u32>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListServerConfigAccessRequest {
+    pub server_config_id: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListServerConfigAccessResponse {
+    pub accesses: Vec<ServerConfigAccess>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerConfigAccess {
+    pub id: u64,
+    pub server_config_id: u64,
+    pub list: AccessList,
+    pub player_name: String,
+    pub player_uuid: String,
+    pub op_level: u32,
+    pub reason: Option<String>,
+    pub create_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteServerConfigAccessRequest {
+    pub id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListOnlinePlayersRequest {
+    pub server_config_id: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListOnlinePlayersResponse {
+    pub players: Vec<OnlinePlayer>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OnlinePlayer {
+    pub name: String,
+    pub uuid: String,
+    pub join_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListPlayerSessionsRequest {
+    pub server_config_id: u64,
+    pub player_name: Option<String>,
+    #[serde(default = "super::default_offset")]
+    pub offset: u64,
+    #[serde(default = "super::default_limit")]
+    pub limit: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListPlayerSessionsResponse {
+    pub count: i64,
+    pub sessions: Vec<PlayerSession>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayerSession {
+    pub id: u64,
+    pub server_config_id: u64,
+    pub player_name: String,
+    pub player_uuid: String,
+    pub join_time: i64,
+    pub leave_time: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GetResourcePackRequest {
     pub id: u64,
+    // 资源包的sha1，只用于让客户端区分不同版本的资源包
+    pub hash: Option<String>,
 }
@@ -0,0 +1,135 @@
+use std::future::Future;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use server_common::{
+    db::{context::Context, dbtype::DBTypeConvertError},
+    impl_sqlx_type,
+};
+use sqlx::{prelude::FromRow, MySql};
+use strum_macros::FromRepr;
+
+#[derive(Debug, Clone, FromRow, Default)]
+pub struct ServerConfigAccess {
+    pub id: u64,
+    pub server_config_id: u64,  // 所属服务器配置
+    pub list_type: AccessList,  // 所属名单
+    pub player_name: String,    // 玩家名
+    pub player_uuid: String,    // 玩家UUID，带连字符
+    pub op_level: u32,          // 管理员权限等级，仅ops名单使用
+    pub reason: Option<String>, // 封禁原因，仅封禁名单使用
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRepr)]
+pub enum AccessList {
+    #[default]
+    Whitelist = 1,
+    Ops = 2,
+    BannedPlayers = 3,
+}
+
+pub trait AccessRepository {
+    fn create_access(
+        &mut self,
+        access: &mut ServerConfigAccess,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn list_access(
+        &mut self,
+        server_config_id: u64,
+    ) -> impl Future<Output = Result<Vec<ServerConfigAccess>>> + Send;
+
+    fn get_access_by_id(
+        &mut self,
+        id: u64,
+    ) -> impl Future<Output = Result<Option<ServerConfigAccess>>> + Send;
+
+    fn delete_access(&mut self, id: u64) -> impl Future<Output = Result<()>> + Send;
+
+    fn delete_access_by_config(
+        &mut self,
+        server_config_id: u64,
+    ) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl_sqlx_type!(AccessList, u32);
+
+impl From<&AccessList> for u32 {
+    fn from(v: &AccessList) -> u32 {
+        *v as u32
+    }
+}
+
+impl TryFrom<u32> for AccessList {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        AccessList::from_repr(value).ok_or(DBTypeConvertError::Anyhow(anyhow!(
+            "Invalid access list: {value}"
+        )))
+    }
+}
+
+impl AccessRepository for Context<'_, MySql> {
+    async fn create_access(&mut self, access: &mut ServerConfigAccess) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            insert into server_config_access (server_config_id, list_type, player_name, player_uuid, op_level, reason)
+            values (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(access.server_config_id)
+        .bind(access.list_type)
+        .bind(&access.player_name)
+        .bind(&access.player_uuid)
+        .bind(access.op_level)
+        .bind(&access.reason)
+        .execute(self)
+        .await?;
+
+        access.id = result.last_insert_id();
+
+        Ok(())
+    }
+
+    async fn list_access(&mut self, server_config_id: u64) -> Result<Vec<ServerConfigAccess>> {
+        let accesses = sqlx::query_as(
+            "select * from server_config_access where server_config_id = ? order by id",
+        )
+        .bind(server_config_id)
+        .fetch_all(self)
+        .await?;
+
+        Ok(accesses)
+    }
+
+    async fn get_access_by_id(&mut self, id: u64) -> Result<Option<ServerConfigAccess>> {
+        let access = sqlx::query_as("select * from server_config_access where id = ?")
+            .bind(id)
+            .fetch_optional(self)
+            .await?;
+
+        Ok(access)
+    }
+
+    async fn delete_access(&mut self, id: u64) -> Result<()> {
+        sqlx::query("delete from server_config_access where id = ?")
+            .bind(id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_access_by_config(&mut self, server_config_id: u64) -> Result<()> {
+        sqlx::query("delete from server_config_access where server_config_id = ?")
+            .bind(server_config_id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+}
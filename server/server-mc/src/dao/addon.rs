@@ -0,0 +1,81 @@
+use std::future::Future;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use server_common::db::context::Context;
+use sqlx::{prelude::FromRow, MySql};
+
+#[derive(Debug, Clone, FromRow, Default)]
+pub struct ServerConfigAddon {
+    pub id: u64,
+    pub server_config_id: u64, // 所属服务器配置
+    pub name: String,          // 文件名
+    pub uri: String,           // 文件地址
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+pub trait AddonRepository {
+    fn create_addon(
+        &mut self,
+        addon: &mut ServerConfigAddon,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn list_addon(
+        &mut self,
+        server_config_id: u64,
+    ) -> impl Future<Output = Result<Vec<ServerConfigAddon>>> + Send;
+
+    fn get_addon_by_id(
+        &mut self,
+        id: u64,
+    ) -> impl Future<Output = Result<Option<ServerConfigAddon>>> + Send;
+
+    fn delete_addon(&mut self, id: u64) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl AddonRepository for Context<'_, MySql> {
+    async fn create_addon(&mut self, addon: &mut ServerConfigAddon) -> Result<()> {
+        let result = sqlx::query(
+            "insert into server_config_addon (server_config_id, name, uri) values (?, ?, ?)",
+        )
+        .bind(addon.server_config_id)
+        .bind(&addon.name)
+        .bind(&addon.uri)
+        .execute(self)
+        .await?;
+
+        addon.id = result.last_insert_id();
+
+        Ok(())
+    }
+
+    async fn list_addon(&mut self, server_config_id: u64) -> Result<Vec<ServerConfigAddon>> {
+        let addons = sqlx::query_as(
+            "select * from server_config_addon where server_config_id = ? order by id",
+        )
+        .bind(server_config_id)
+        .fetch_all(self)
+        .await?;
+
+        Ok(addons)
+    }
+
+    async fn get_addon_by_id(&mut self, id: u64) -> Result<Option<ServerConfigAddon>> {
+        let addon = sqlx::query_as("select * from server_config_addon where id = ?")
+            .bind(id)
+            .fetch_optional(self)
+            .await?;
+
+        Ok(addon)
+    }
+
+    async fn delete_addon(&mut self, id: u64) -> Result<()> {
+        sqlx::query("delete from server_config_addon where id = ?")
+            .bind(id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use std::future::Future;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use server_common::db::{context::Context, count::Counter};
+use sqlx::{prelude::FromRow, MySql, QueryBuilder};
+
+#[derive(Debug, Clone, FromRow, Default)]
+pub struct ServerConfigBackup {
+    pub id: u64,
+    pub server_config_id: u64, // 所属服务器配置
+    pub uri: String,           // 备份存档地址
+    pub size: u64,             // 备份文件大小
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+pub struct ListBackupParameters {
+    pub server_config_id: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+pub trait BackupRepository {
+    fn create_backup(
+        &mut self,
+        backup: &mut ServerConfigBackup,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn list_backup(
+        &mut self,
+        params: &ListBackupParameters,
+    ) -> impl Future<Output = Result<Vec<ServerConfigBackup>>> + Send;
+
+    fn count_backup(
+        &mut self,
+        params: &ListBackupParameters,
+    ) -> impl Future<Output = Result<i64>> + Send;
+
+    fn get_backup_by_id(
+        &mut self,
+        id: u64,
+    ) -> impl Future<Output = Result<Option<ServerConfigBackup>>> + Send;
+
+    fn delete_backup(&mut self, id: u64) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl BackupRepository for Context<'_, MySql> {
+    async fn create_backup(&mut self, backup: &mut ServerConfigBackup) -> Result<()> {
+        let result = sqlx::query(
+            "insert into server_config_backup (server_config_id, uri, size) values (?, ?, ?)",
+        )
+        .bind(backup.server_config_id)
+        .bind(&backup.uri)
+        .bind(backup.size)
+        .execute(self)
+        .await?;
+
+        backup.id = result.last_insert_id();
+
+        Ok(())
+    }
+
+    async fn list_backup(
+        &mut self,
+        params: &ListBackupParameters,
+    ) -> Result<Vec<ServerConfigBackup>> {
+        let mut query = QueryBuilder::new("select * from server_config_backup");
+        params.append_where_clause(&mut query);
+        query.push(" order by id desc");
+        query
+            .push(" limit ")
+            .push_bind(params.offset)
+            .push(", ")
+            .push_bind(params.limit);
+
+        Ok(query.build_query_as().fetch_all(self).await?)
+    }
+
+    async fn count_backup(&mut self, params: &ListBackupParameters) -> Result<i64> {
+        let mut query = QueryBuilder::new("select count(*) from server_config_backup");
+        params.append_where_clause(&mut query);
+        let count: Counter = query.build_query_as().fetch_one(self).await?;
+        Ok(count.count)
+    }
+
+    async fn get_backup_by_id(&mut self, id: u64) -> Result<Option<ServerConfigBackup>> {
+        let backup = sqlx::query_as("select * from server_config_backup where id = ?")
+            .bind(id)
+            .fetch_optional(self)
+            .await?;
+
+        Ok(backup)
+    }
+
+    async fn delete_backup(&mut self, id: u64) -> Result<()> {
+        sqlx::query("delete from server_config_backup where id = ?")
+            .bind(id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl ListBackupParameters {
+    fn append_where_clause<'args>(&'args self, query_builder: &mut QueryBuilder<'args, MySql>) {
+        query_builder
+            .push(" where server_config_id = ")
+            .push_bind(self.server_config_id);
+    }
+}
@@ -1,2 +1,7 @@
+pub mod access;
+pub mod addon;
+pub mod backup;
+pub mod player_session;
 pub mod server_config;
+pub mod server_run;
 pub mod version;
@@ -0,0 +1,136 @@
+use std::future::Future;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use server_common::db::{bound_separated::BoundSeparatedHelper, context::Context, count::Counter};
+use sqlx::{prelude::FromRow, MySql, QueryBuilder};
+
+#[derive(Debug, Clone, FromRow, Default)]
+pub struct PlayerSession {
+    pub id: u64,
+    pub server_config_id: u64,             // 所属服务器配置
+    pub player_name: String,               // 玩家名
+    pub player_uuid: String,               // 玩家UUID，离线模式下也会生成
+    pub join_time: DateTime<Utc>,          // 进入时间
+    pub leave_time: Option<DateTime<Utc>>, // 离开时间，为空表示仍在线
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+pub struct ListPlayerSessionParameters {
+    pub server_config_id: u64,
+    pub player_name: Option<String>,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+pub trait PlayerSessionRepository {
+    fn create_player_session(
+        &mut self,
+        session: &mut PlayerSession,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn finish_player_session(
+        &mut self,
+        id: u64,
+        leave_time: DateTime<Utc>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    // 服务器停止时仍未结束的会话
+    fn finish_open_player_sessions(
+        &mut self,
+        server_config_id: u64,
+        leave_time: DateTime<Utc>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn list_player_session(
+        &mut self,
+        params: &ListPlayerSessionParameters,
+    ) -> impl Future<Output = Result<Vec<PlayerSession>>> + Send;
+
+    fn count_player_session(
+        &mut self,
+        params: &ListPlayerSessionParameters,
+    ) -> impl Future<Output = Result<i64>> + Send;
+}
+
+impl PlayerSessionRepository for Context<'_, MySql> {
+    async fn create_player_session(&mut self, session: &mut PlayerSession) -> Result<()> {
+        let result = sqlx::query(
+            "insert into player_session (server_config_id, player_name, player_uuid, join_time) values (?, ?, ?, ?)",
+        )
+        .bind(session.server_config_id)
+        .bind(&session.player_name)
+        .bind(&session.player_uuid)
+        .bind(session.join_time)
+        .execute(self)
+        .await?;
+
+        session.id = result.last_insert_id();
+
+        Ok(())
+    }
+
+    async fn finish_player_session(&mut self, id: u64, leave_time: DateTime<Utc>) -> Result<()> {
+        sqlx::query("update player_session set leave_time = ? where id = ? and leave_time is null")
+            .bind(leave_time)
+            .bind(id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn finish_open_player_sessions(
+        &mut self,
+        server_config_id: u64,
+        leave_time: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "update player_session set leave_time = ? where server_config_id = ? and leave_time is null",
+        )
+        .bind(leave_time)
+        .bind(server_config_id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_player_session(
+        &mut self,
+        params: &ListPlayerSessionParameters,
+    ) -> Result<Vec<PlayerSession>> {
+        let mut query = QueryBuilder::new("select * from player_session");
+        params.append_where_clause(&mut query);
+        query.push(" order by join_time desc, id desc");
+        query
+            .push(" limit ")
+            .push_bind(params.offset)
+            .push(", ")
+            .push_bind(params.limit);
+
+        Ok(query.build_query_as().fetch_all(self).await?)
+    }
+
+    async fn count_player_session(&mut self, params: &ListPlayerSessionParameters) -> Result<i64> {
+        let mut query = QueryBuilder::new("select count(*) from player_session");
+        params.append_where_clause(&mut query);
+        let count: Counter = query.build_query_as().fetch_one(self).await?;
+        Ok(count.count)
+    }
+}
+
+impl ListPlayerSessionParameters {
+    fn append_where_clause<'args>(&'args self, query_builder: &mut QueryBuilder<'args, MySql>) {
+        let mut where_clause = query_builder.bound_separated(" where", "", " and");
+        where_clause
+            .push(" server_config_id = ")
+            .push_bind_unseparated(self.server_config_id);
+        if let Some(player_name) = &self.player_name {
+            where_clause
+                .push(" player_name = ")
+                .push_bind_unseparated(player_name);
+        }
+    }
+}
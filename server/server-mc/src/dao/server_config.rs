@@ -1,22 +1,110 @@
 use std::future::Future;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use chrono::{DateTime, Utc};
-use server_common::db::{context::Context, count::Counter};
+use serde::{Deserialize, Serialize};
+use server_common::{
+    db::{context::Context, count::Counter, dbtype::DBTypeConvertError},
+    impl_sqlx_type,
+};
 use sqlx::{prelude::FromRow, MySql, QueryBuilder};
+use strum_macros::{Display, FromRepr};
 
 #[derive(Debug, Clone, FromRow, Default)]
 pub struct ServerConfig {
     pub id: u64,
-    pub name: String,                 // 服务器配置名，用于管理
-    pub mc_version: String,           // mc版本号
-    pub world_uri: Option<String>,    // 存档地址
-    pub resource_uri: Option<String>, // 资源包地址
-    pub motd: String,                 // 服务器motd
+    pub name: String,                  // 服务器配置名，用于管理
+    pub mc_version: String,            // mc版本号
+    pub flavor: ServerFlavor,          // 服务端类型
+    pub world_uri: Option<String>,     // 存档地址
+    pub resource_uri: Option<String>,  // 资源包地址
+    pub motd: String,                  // 服务器motd
+    pub restart_policy: RestartPolicy, // 进程异常退出后的重启策略
+    pub restart_max_retries: u32,      // on-failure策略下的最大连续重试次数
+    pub properties: ServerProperties,  // server.properties中可配置的项
+    pub jvm_options: JvmOptions,       // 启动参数
+    pub desired_running: bool,         // 期望运行状态，server-mc重启后据此恢复运行
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
 
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRepr)]
+pub enum RestartPolicy {
+    #[default]
+    Never = 1,
+    OnFailure = 2,
+    Always = 3,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRepr, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ServerFlavor {
+    #[default]
+    Vanilla = 1,
+    Paper = 2,
+    Fabric = 3,
+}
+
+// 未设置的项使用默认值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerProperties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gamemode: Option<GameMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_players: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pvp: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level_seed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub white_list: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub online_mode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view_distance: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_command_block: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_resource_pack: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+// 内存大小单位为MB，未设置时由启动器决定
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JvmOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_memory_mb: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+    #[serde(default)]
+    pub aikar_flags: bool,
+}
+
 pub struct ListServerConfigParameters {
     pub offset: u64,
     pub limit: u64,
@@ -25,9 +113,15 @@ pub struct ListServerConfigParameters {
 pub enum UpdateServerConfig<'v> {
     Name(&'v str),
     McVersion(&'v str),
+    Flavor(ServerFlavor),
     WorldUri(Option<&'v String>),
     ResourceUri(Option<&'v String>),
     Motd(&'v str),
+    RestartPolicy(RestartPolicy),
+    RestartMaxRetries(u32),
+    Properties(&'v ServerProperties),
+    JvmOptions(&'v JvmOptions),
+    DesiredRunning(bool),
 }
 
 pub trait ServerConfigRepository {
@@ -62,22 +156,220 @@ pub trait ServerConfigRepository {
         updates: &[UpdateServerConfig<'_>],
     ) -> impl Future<Output = Result<()>> + Send;
 
+    fn list_desired_running_server_config(
+        &mut self,
+    ) -> impl Future<Output = Result<Vec<ServerConfig>>> + Send;
+
     fn delete_server_config(&mut self, id: u64) -> impl Future<Output = Result<()>> + Send;
 }
 
+impl_sqlx_type!(RestartPolicy, u32);
+
+impl From<&RestartPolicy> for u32 {
+    fn from(v: &RestartPolicy) -> u32 {
+        *v as u32
+    }
+}
+
+impl TryFrom<u32> for RestartPolicy {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        RestartPolicy::from_repr(value).ok_or(DBTypeConvertError::Anyhow(anyhow!(
+            "Invalid restart policy: {value}"
+        )))
+    }
+}
+
+impl_sqlx_type!(ServerFlavor, u32);
+
+impl From<&ServerFlavor> for u32 {
+    fn from(v: &ServerFlavor) -> u32 {
+        *v as u32
+    }
+}
+
+impl TryFrom<u32> for ServerFlavor {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        ServerFlavor::from_repr(value).ok_or(DBTypeConvertError::Anyhow(anyhow!(
+            "Invalid server flavor: {value}"
+        )))
+    }
+}
+
+impl ServerProperties {
+    pub fn validate(&self) -> Result<()> {
+        if self.max_players.is_some_and(|v| !(1..=1000).contains(&v)) {
+            bail!("max-players should be between 1 and 1000");
+        }
+
+        if self.view_distance.is_some_and(|v| !(3..=32).contains(&v)) {
+            bail!("view-distance should be between 3 and 32");
+        }
+
+        if self
+            .level_seed
+            .as_ref()
+            .is_some_and(|v| v.len() > 64 || v.contains(['\r', '\n']))
+        {
+            bail!("invalid level-seed");
+        }
+
+        Ok(())
+    }
+
+    // 当前未设置的项使用defaults中的值
+    pub fn merge(&self, defaults: &ServerProperties) -> ServerProperties {
+        ServerProperties {
+            gamemode: self.gamemode.or(defaults.gamemode),
+            difficulty: self.difficulty.or(defaults.difficulty),
+            max_players: self.max_players.or(defaults.max_players),
+            pvp: self.pvp.or(defaults.pvp),
+            level_seed: self.level_seed.clone().or(defaults.level_seed.clone()),
+            white_list: self.white_list.or(defaults.white_list),
+            online_mode: self.online_mode.or(defaults.online_mode),
+            view_distance: self.view_distance.or(defaults.view_distance),
+            enable_command_block: self.enable_command_block.or(defaults.enable_command_block),
+            require_resource_pack: self
+                .require_resource_pack
+                .or(defaults.require_resource_pack),
+        }
+    }
+
+    // server.properties中的键值对
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        [
+            ("gamemode", self.gamemode.map(|v| v.to_string())),
+            ("difficulty", self.difficulty.map(|v| v.to_string())),
+            ("max-players", self.max_players.map(|v| v.to_string())),
+            ("pvp", self.pvp.map(|v| v.to_string())),
+            ("level-seed", self.level_seed.clone()),
+            ("white-list", self.white_list.map(|v| v.to_string())),
+            ("online-mode", self.online_mode.map(|v| v.to_string())),
+            ("view-distance", self.view_distance.map(|v| v.to_string())),
+            (
+                "enable-command-block",
+                self.enable_command_block.map(|v| v.to_string()),
+            ),
+            (
+                "require-resource-pack",
+                self.require_resource_pack.map(|v| v.to_string()),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect()
+    }
+}
+
+impl_sqlx_type!(ServerProperties, String);
+
+impl From<&ServerProperties> for String {
+    fn from(v: &ServerProperties) -> String {
+        serde_json::to_string(v).expect("server properties should be serializable")
+    }
+}
+
+impl TryFrom<String> for ServerProperties {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Ok(ServerProperties::default());
+        }
+
+        serde_json::from_str(&value).map_err(|e| DBTypeConvertError::BoxStd(Box::new(e)))
+    }
+}
+
+impl JvmOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.min_memory_mb.is_some_and(|v| v == 0) || self.max_memory_mb.is_some_and(|v| v == 0)
+        {
+            bail!("memory should be greater than 0");
+        }
+
+        if let (Some(min), Some(max)) = (self.min_memory_mb, self.max_memory_mb) {
+            if min > max {
+                bail!("min memory should not be greater than max memory");
+            }
+        }
+
+        if self.extra_args.len() > 32 {
+            bail!("too many jvm args");
+        }
+
+        // 堆大小只能通过内存配置设置，避免绕过上限；
+        // 参数文件（@argfiles、-XX:VMOptionsFile、-XX:Flags）中的参数无法检查，同样不允许
+        for arg in &self.extra_args {
+            if !arg.starts_with('-') || arg.len() > 256 || arg.contains(char::is_whitespace) {
+                bail!("invalid jvm arg: {arg}");
+            }
+
+            if [
+                "-Xmx",
+                "-Xms",
+                "-XX:MaxRAM",
+                "-XX:MaxHeapSize",
+                "-XX:InitialHeapSize",
+                "-XX:MinHeapSize",
+                "-XX:VMOptionsFile",
+                "-XX:Flags",
+                "-jar",
+                "-cp",
+                "-classpath",
+            ]
+            .iter()
+            .any(|prefix| arg.starts_with(prefix))
+            {
+                bail!("jvm arg is not allowed: {arg}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl_sqlx_type!(JvmOptions, String);
+
+impl From<&JvmOptions> for String {
+    fn from(v: &JvmOptions) -> String {
+        serde_json::to_string(v).expect("jvm options should be serializable")
+    }
+}
+
+impl TryFrom<String> for JvmOptions {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Ok(JvmOptions::default());
+        }
+
+        serde_json::from_str(&value).map_err(|e| DBTypeConvertError::BoxStd(Box::new(e)))
+    }
+}
+
 impl ServerConfigRepository for Context<'_, MySql> {
     async fn create_server_config(&mut self, server_config: &mut ServerConfig) -> Result<()> {
         let result = sqlx::query(
             r#"
-            INSERT INTO server_config (name, mc_version, world_uri, resource_uri, motd)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO server_config (name, mc_version, flavor, world_uri, resource_uri, motd, restart_policy, restart_max_retries, properties, jvm_options)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&server_config.name)
         .bind(&server_config.mc_version)
+        .bind(server_config.flavor)
         .bind(&server_config.world_uri)
         .bind(&server_config.resource_uri)
         .bind(&server_config.motd)
+        .bind(server_config.restart_policy)
+        .bind(server_config.restart_max_retries)
+        .bind(&server_config.properties)
+        .bind(&server_config.jvm_options)
         .execute(self)
         .await?;
 
@@ -147,6 +439,11 @@ impl ServerConfigRepository for Context<'_, MySql> {
                             .push(" mc_version = ")
                             .push_bind_unseparated(mc_version);
                     }
+                    UpdateServerConfig::Flavor(flavor) => {
+                        query_update
+                            .push(" flavor = ")
+                            .push_bind_unseparated(*flavor);
+                    }
                     UpdateServerConfig::WorldUri(world_uri) => {
                         query_update
                             .push(" world_uri = ")
@@ -160,6 +457,31 @@ impl ServerConfigRepository for Context<'_, MySql> {
                     UpdateServerConfig::Motd(motd) => {
                         query_update.push(" motd = ").push_bind_unseparated(motd);
                     }
+                    UpdateServerConfig::RestartPolicy(restart_policy) => {
+                        query_update
+                            .push(" restart_policy = ")
+                            .push_bind_unseparated(*restart_policy);
+                    }
+                    UpdateServerConfig::RestartMaxRetries(restart_max_retries) => {
+                        query_update
+                            .push(" restart_max_retries = ")
+                            .push_bind_unseparated(*restart_max_retries);
+                    }
+                    UpdateServerConfig::Properties(properties) => {
+                        query_update
+                            .push(" properties = ")
+                            .push_bind_unseparated(*properties);
+                    }
+                    UpdateServerConfig::JvmOptions(jvm_options) => {
+                        query_update
+                            .push(" jvm_options = ")
+                            .push_bind_unseparated(*jvm_options);
+                    }
+                    UpdateServerConfig::DesiredRunning(desired_running) => {
+                        query_update
+                            .push(" desired_running = ")
+                            .push_bind_unseparated(*desired_running);
+                    }
                 }
             }
         }
@@ -170,6 +492,15 @@ impl ServerConfigRepository for Context<'_, MySql> {
         Ok(())
     }
 
+    async fn list_desired_running_server_config(&mut self) -> Result<Vec<ServerConfig>> {
+        let server_configs =
+            sqlx::query_as("select * from server_config where desired_running = true order by id")
+                .fetch_all(self)
+                .await?;
+
+        Ok(server_configs)
+    }
+
     async fn delete_server_config(&mut self, id: u64) -> Result<()> {
         sqlx::query("delete from server_config where id = ?")
             .bind(id)
@@ -0,0 +1,241 @@
+use std::future::Future;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use server_common::{
+    db::{context::Context, count::Counter, dbtype::DBTypeConvertError},
+    impl_sqlx_type,
+};
+use sqlx::{prelude::FromRow, MySql, QueryBuilder};
+use strum_macros::FromRepr;
+
+#[derive(Debug, Clone, FromRow, Default)]
+pub struct ServerRun {
+    pub id: u64,
+    pub server_config_id: u64,               // 所属服务器配置
+    pub start_time: DateTime<Utc>,           // 开始启动时间
+    pub stop_time: DateTime<Utc>,            // 进程结束时间
+    pub result: ServerRunResult,             // 结束方式
+    pub final_stage: Option<ServerRunStage>, // 结束前所处阶段，进入第一个阶段前失败时为空
+    pub error: Option<String>,               // 失败或崩溃原因
+    pub exit_code: Option<i32>,              // 进程退出码
+    pub stages: ServerRunStages,             // 各阶段耗时
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromRepr)]
+pub enum ServerRunResult {
+    #[default]
+    Stopped = 1,
+    Crashed = 2,
+    Failed = 3,
+    Cancelled = 4,
+    Running = 5,     // 运行中，结束时更新
+    Interrupted = 6, // 运行中服务被终止，没能记录结束方式
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromRepr, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerRunStage {
+    SelectJavaRuntime = 1,
+    DownloadServerJar = 2,
+    DownloadWorld = 3,
+    InstallAddons = 4,
+    InitializeConfigFile = 5,
+    WaitingForServerReady = 6,
+    Running = 7,
+    Terminating = 8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerRunStageInfo {
+    pub stage: ServerRunStage,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+// 按进入时间排序
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerRunStages(pub Vec<ServerRunStageInfo>);
+
+pub struct ListServerRunParameters {
+    pub server_config_id: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+pub trait ServerRunRepository {
+    fn create_server_run(&mut self, run: &mut ServerRun)
+        -> impl Future<Output = Result<()>> + Send;
+
+    fn finish_server_run(&mut self, run: &ServerRun) -> impl Future<Output = Result<()>> + Send;
+
+    fn interrupt_running_server_runs(
+        &mut self,
+        stop_time: DateTime<Utc>,
+    ) -> impl Future<Output = Result<u64>> + Send;
+
+    fn list_server_run(
+        &mut self,
+        params: &ListServerRunParameters,
+    ) -> impl Future<Output = Result<Vec<ServerRun>>> + Send;
+
+    fn count_server_run(
+        &mut self,
+        params: &ListServerRunParameters,
+    ) -> impl Future<Output = Result<i64>> + Send;
+}
+
+impl_sqlx_type!(ServerRunResult, u32);
+
+impl From<&ServerRunResult> for u32 {
+    fn from(v: &ServerRunResult) -> u32 {
+        *v as u32
+    }
+}
+
+impl TryFrom<u32> for ServerRunResult {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        ServerRunResult::from_repr(value).ok_or(DBTypeConvertError::Anyhow(anyhow!(
+            "Invalid server run result: {value}"
+        )))
+    }
+}
+
+impl_sqlx_type!(ServerRunStage, u32);
+
+impl From<&ServerRunStage> for u32 {
+    fn from(v: &ServerRunStage) -> u32 {
+        *v as u32
+    }
+}
+
+impl TryFrom<u32> for ServerRunStage {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        ServerRunStage::from_repr(value).ok_or(DBTypeConvertError::Anyhow(anyhow!(
+            "Invalid server run stage: {value}"
+        )))
+    }
+}
+
+impl_sqlx_type!(ServerRunStages, String);
+
+impl From<&ServerRunStages> for String {
+    fn from(v: &ServerRunStages) -> String {
+        serde_json::to_string(v).expect("server run stages should be serializable")
+    }
+}
+
+impl TryFrom<String> for ServerRunStages {
+    type Error = DBTypeConvertError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Ok(ServerRunStages::default());
+        }
+
+        serde_json::from_str(&value).map_err(|e| DBTypeConvertError::BoxStd(Box::new(e)))
+    }
+}
+
+impl ServerRunRepository for Context<'_, MySql> {
+    async fn create_server_run(&mut self, run: &mut ServerRun) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            insert into server_run (
+                server_config_id, start_time, stop_time, result, final_stage, error, exit_code, stages
+            ) values (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(run.server_config_id)
+        .bind(run.start_time)
+        .bind(run.stop_time)
+        .bind(run.result)
+        .bind(run.final_stage)
+        .bind(&run.error)
+        .bind(run.exit_code)
+        .bind(&run.stages)
+        .execute(self)
+        .await?;
+
+        run.id = result.last_insert_id();
+
+        Ok(())
+    }
+
+    async fn finish_server_run(&mut self, run: &ServerRun) -> Result<()> {
+        sqlx::query(
+            r#"
+            update server_run
+            set stop_time = ?, result = ?, final_stage = ?, error = ?, exit_code = ?, stages = ?
+            where id = ?
+            "#,
+        )
+        .bind(run.stop_time)
+        .bind(run.result)
+        .bind(run.final_stage)
+        .bind(&run.error)
+        .bind(run.exit_code)
+        .bind(&run.stages)
+        .bind(run.id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn interrupt_running_server_runs(&mut self, stop_time: DateTime<Utc>) -> Result<u64> {
+        let result =
+            sqlx::query("update server_run set stop_time = ?, result = ? where result = ?")
+                .bind(stop_time)
+                .bind(ServerRunResult::Interrupted)
+                .bind(ServerRunResult::Running)
+                .execute(self)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn list_server_run(
+        &mut self,
+        params: &ListServerRunParameters,
+    ) -> Result<Vec<ServerRun>> {
+        let mut query = QueryBuilder::new("select * from server_run");
+        params.append_where_clause(&mut query);
+        query.push(" order by start_time desc, id desc");
+        query
+            .push(" limit ")
+            .push_bind(params.offset)
+            .push(", ")
+            .push_bind(params.limit);
+
+        Ok(query.build_query_as().fetch_all(self).await?)
+    }
+
+    async fn count_server_run(&mut self, params: &ListServerRunParameters) -> Result<i64> {
+        let mut query = QueryBuilder::new("select count(*) from server_run");
+        params.append_where_clause(&mut query);
+        let count: Counter = query.build_query_as().fetch_one(self).await?;
+        Ok(count.count)
+    }
+}
+
+impl ListServerRunParameters {
+    fn append_where_clause<'args>(&'args self, query_builder: &mut QueryBuilder<'args, MySql>) {
+        query_builder
+            .push(" where server_config_id = ")
+            .push_bind(self.server_config_id);
+    }
+}
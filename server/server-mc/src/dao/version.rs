@@ -18,6 +18,10 @@ pub struct Version {
     pub mc_id: String,
     pub r#type: VersionType,
     pub server_url: String,
+    pub java_major_version: u32, // 运行所需的java主版本号，0表示未知
+    pub server_sha1: String,     // 服务端程序的sha1，空表示未知
+    pub server_size: u64,        // 服务端程序的文件大小
+    pub detail_checked: bool,    // 是否已从版本详情补全，详情中缺失的字段不再重复获取
     pub release_time: DateTime<Utc>,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
@@ -56,6 +60,13 @@ pub trait VersionRepository {
         &mut self,
         mcid: &str,
     ) -> impl Future<Output = Result<Option<Version>>> + Send;
+
+    fn list_version_incomplete(&mut self) -> impl Future<Output = Result<Vec<Version>>> + Send;
+
+    fn update_version_detail(
+        &mut self,
+        version: &Version,
+    ) -> impl Future<Output = Result<()>> + Send;
 }
 
 impl_sqlx_type!(VersionType, u32);
@@ -79,11 +90,15 @@ impl TryFrom<u32> for VersionType {
 impl VersionRepository for Context<'_, MySql> {
     async fn create_version(&mut self, version: &mut Version) -> Result<()> {
         let result = sqlx::query(
-            "insert into mc_version (mc_id, type, server_url, release_time) values (?, ?, ?, ?)",
+            "insert into mc_version (mc_id, type, server_url, java_major_version, server_sha1, server_size, detail_checked, release_time) values (?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&version.mc_id)
         .bind(version.r#type)
         .bind(&version.server_url)
+        .bind(version.java_major_version)
+        .bind(&version.server_sha1)
+        .bind(version.server_size)
+        .bind(version.detail_checked)
         .bind(version.release_time)
         .execute(self)
         .await?;
@@ -121,6 +136,30 @@ impl VersionRepository for Context<'_, MySql> {
 
         Ok(version)
     }
+
+    async fn list_version_incomplete(&mut self) -> Result<Vec<Version>> {
+        let versions = sqlx::query_as(
+            "select * from mc_version where detail_checked = 0 and (java_major_version = 0 or server_sha1 = '')",
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(versions)
+    }
+
+    async fn update_version_detail(&mut self, version: &Version) -> Result<()> {
+        sqlx::query(
+            "update mc_version set java_major_version = ?, server_sha1 = ?, server_size = ?, detail_checked = 1 where id = ?",
+        )
+        .bind(version.java_major_version)
+        .bind(&version.server_sha1)
+        .bind(version.server_size)
+        .bind(version.id)
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
 }
 
 impl ListVersionParameters {
@@ -0,0 +1,53 @@
+use common::tonic_idl_gen::*;
+use server_common::db::context::Context;
+use tonic::{Response, Status};
+
+use crate::{service, Service};
+
+pub async fn add_server_config_access(
+    service: &Service,
+    req: AddServerConfigAccessRequest,
+) -> Result<Response<AddServerConfigAccessResponse>, Status> {
+    let result = service::access::add_server_config_access(
+        &mut Context::PoolRef(&service.db),
+        &service.http_client,
+        &service.process_manager,
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn list_server_config_access(
+    service: &Service,
+    req: ListServerConfigAccessRequest,
+) -> Result<Response<ListServerConfigAccessResponse>, Status> {
+    let result =
+        service::access::list_server_config_access(&mut Context::PoolRef(&service.db), req).await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn delete_server_config_access(
+    service: &Service,
+    req: DeleteServerConfigAccessRequest,
+) -> Result<Response<DeleteServerConfigAccessResponse>, Status> {
+    let result = service::access::delete_server_config_access(
+        &mut Context::PoolRef(&service.db),
+        &service.process_manager,
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
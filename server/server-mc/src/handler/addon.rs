@@ -0,0 +1,52 @@
+use common::tonic_idl_gen::*;
+use server_common::db::context::Context;
+use tonic::{Response, Status};
+
+use crate::{service, Service};
+
+pub async fn add_server_config_addon(
+    service: &Service,
+    req: AddServerConfigAddonRequest,
+) -> Result<Response<AddServerConfigAddonResponse>, Status> {
+    let result = service::addon::add_server_config_addon(
+        &mut Context::PoolRef(&service.db),
+        service.oss_client.with_http(&service.http_client),
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn list_server_config_addon(
+    service: &Service,
+    req: ListServerConfigAddonRequest,
+) -> Result<Response<ListServerConfigAddonResponse>, Status> {
+    let result =
+        service::addon::list_server_config_addon(&mut Context::PoolRef(&service.db), req).await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn delete_server_config_addon(
+    service: &Service,
+    req: DeleteServerConfigAddonRequest,
+) -> Result<Response<DeleteServerConfigAddonResponse>, Status> {
+    let result = service::addon::delete_server_config_addon(
+        &mut Context::PoolRef(&service.db),
+        service.oss_client.with_http(&service.http_client),
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
@@ -0,0 +1,53 @@
+use common::tonic_idl_gen::*;
+use server_common::db::context::Context;
+use tonic::{Response, Status};
+
+use crate::{service, Service};
+
+pub async fn list_server_config_backup(
+    service: &Service,
+    req: ListServerConfigBackupRequest,
+) -> Result<Response<ListServerConfigBackupResponse>, Status> {
+    let result =
+        service::backup::list_server_config_backup(&mut Context::PoolRef(&service.db), req).await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn restore_server_config_backup(
+    service: &Service,
+    req: RestoreServerConfigBackupRequest,
+) -> Result<Response<RestoreServerConfigBackupResponse>, Status> {
+    let result = service::backup::restore_server_config_backup(
+        &mut Context::PoolRef(&service.db),
+        service.oss_client.with_http(&service.http_client),
+        &service.process_manager,
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn delete_server_config_backup(
+    service: &Service,
+    req: DeleteServerConfigBackupRequest,
+) -> Result<Response<DeleteServerConfigBackupResponse>, Status> {
+    let result = service::backup::delete_server_config_backup(
+        &mut Context::PoolRef(&service.db),
+        service.oss_client.with_http(&service.http_client),
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
@@ -4,12 +4,19 @@ use tonic::{Request, Response, Status};
 
 use crate::Service;
 
+pub mod access;
+pub mod addon;
+pub mod backup;
+pub mod player;
 pub mod process;
 pub mod server_config;
+pub mod server_run;
 pub mod version;
 
 #[tonic::async_trait]
 impl McService for Service {
+    type StreamServerConsoleStream = process::StreamServerConsoleStream;
+
     async fn list_mc_version(
         &self,
         req: Request<ListMcVersionRequest>,
@@ -38,6 +45,13 @@ impl McService for Service {
         server_config::list_server_config(self, req.into_inner()).await
     }
 
+    async fn update_server_config(
+        &self,
+        req: Request<UpdateServerConfigRequest>,
+    ) -> Result<Response<UpdateServerConfigResponse>, Status> {
+        server_config::update_server_config(self, req.into_inner()).await
+    }
+
     async fn delete_server_config(
         &self,
         req: Request<DeleteServerConfigRequest>,
@@ -59,10 +73,108 @@ impl McService for Service {
         process::stop_server_config(self, req.into_inner()).await
     }
 
-    async fn get_current_server_config(
+    async fn list_running_servers(
+        &self,
+        req: Request<ListRunningServersRequest>,
+    ) -> Result<Response<ListRunningServersResponse>, Status> {
+        process::list_running_servers(self, req.into_inner()).await
+    }
+
+    async fn stream_server_console(
+        &self,
+        req: Request<StreamServerConsoleRequest>,
+    ) -> Result<Response<Self::StreamServerConsoleStream>, Status> {
+        process::stream_server_console(self, req.into_inner()).await
+    }
+
+    async fn send_server_command(
+        &self,
+        req: Request<SendServerCommandRequest>,
+    ) -> Result<Response<SendServerCommandResponse>, Status> {
+        process::send_server_command(self, req.into_inner()).await
+    }
+
+    async fn list_server_config_backup(
+        &self,
+        req: Request<ListServerConfigBackupRequest>,
+    ) -> Result<Response<ListServerConfigBackupResponse>, Status> {
+        backup::list_server_config_backup(self, req.into_inner()).await
+    }
+
+    async fn restore_server_config_backup(
+        &self,
+        req: Request<RestoreServerConfigBackupRequest>,
+    ) -> Result<Response<RestoreServerConfigBackupResponse>, Status> {
+        backup::restore_server_config_backup(self, req.into_inner()).await
+    }
+
+    async fn delete_server_config_backup(
+        &self,
+        req: Request<DeleteServerConfigBackupRequest>,
+    ) -> Result<Response<DeleteServerConfigBackupResponse>, Status> {
+        backup::delete_server_config_backup(self, req.into_inner()).await
+    }
+
+    async fn add_server_config_addon(
+        &self,
+        req: Request<AddServerConfigAddonRequest>,
+    ) -> Result<Response<AddServerConfigAddonResponse>, Status> {
+        addon::add_server_config_addon(self, req.into_inner()).await
+    }
+
+    async fn list_server_config_addon(
+        &self,
+        req: Request<ListServerConfigAddonRequest>,
+    ) -> Result<Response<ListServerConfigAddonResponse>, Status> {
+        addon::list_server_config_addon(self, req.into_inner()).await
+    }
+
+    async fn delete_server_config_addon(
+        &self,
+        req: Request<DeleteServerConfigAddonRequest>,
+    ) -> Result<Response<DeleteServerConfigAddonResponse>, Status> {
+        addon::delete_server_config_addon(self, req.into_inner()).await
+    }
+
+    async fn list_online_players(
+        &self,
+        req: Request<ListOnlinePlayersRequest>,
+    ) -> Result<Response<ListOnlinePlayersResponse>, Status> {
+        player::list_online_players(self, req.into_inner()).await
+    }
+
+    async fn list_player_sessions(
+        &self,
+        req: Request<ListPlayerSessionsRequest>,
+    ) -> Result<Response<ListPlayerSessionsResponse>, Status> {
+        player::list_player_sessions(self, req.into_inner()).await
+    }
+
+    async fn list_server_runs(
+        &self,
+        req: Request<ListServerRunsRequest>,
+    ) -> Result<Response<ListServerRunsResponse>, Status> {
+        server_run::list_server_runs(self, req.into_inner()).await
+    }
+
+    async fn add_server_config_access(
+        &self,
+        req: Request<AddServerConfigAccessRequest>,
+    ) -> Result<Response<AddServerConfigAccessResponse>, Status> {
+        access::add_server_config_access(self, req.into_inner()).await
+    }
+
+    async fn list_server_config_access(
+        &self,
+        req: Request<ListServerConfigAccessRequest>,
+    ) -> Result<Response<ListServerConfigAccessResponse>, Status> {
+        access::list_server_config_access(self, req.into_inner()).await
+    }
+
+    async fn delete_server_config_access(
         &self,
-        req: Request<GetCurrentServerConfigRequest>,
-    ) -> Result<Response<GetCurrentServerConfigResponse>, Status> {
-        process::get_current_server_config(self, req.into_inner()).await
+        req: Request<DeleteServerConfigAccessRequest>,
+    ) -> Result<Response<DeleteServerConfigAccessResponse>, Status> {
+        access::delete_server_config_access(self, req.into_inner()).await
     }
 }
@@ -0,0 +1,30 @@
+use common::tonic_idl_gen::*;
+use server_common::db::context::Context;
+use tonic::{Response, Status};
+
+use crate::{service, Service};
+
+pub async fn list_online_players(
+    service: &Service,
+    req: ListOnlinePlayersRequest,
+) -> Result<Response<ListOnlinePlayersResponse>, Status> {
+    let result = service::player::list_online_players(&service.process_manager, req).await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn list_player_sessions(
+    service: &Service,
+    req: ListPlayerSessionsRequest,
+) -> Result<Response<ListPlayerSessionsResponse>, Status> {
+    let result =
+        service::player::list_player_sessions(&mut Context::PoolRef(&service.db), req).await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
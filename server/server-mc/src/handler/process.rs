@@ -1,9 +1,12 @@
 use common::tonic_idl_gen::*;
+use futures_util::{stream::BoxStream, StreamExt};
 use server_common::db::context::Context;
 use tonic::{Response, Status};
 
 use crate::{service, Service};
 
+pub type StreamServerConsoleStream = BoxStream<'static, Result<ServerConsoleLine, Status>>;
+
 pub async fn start_server_config(
     service: &Service,
     req: StartServerConfigRequest,
@@ -25,7 +28,24 @@ pub async fn stop_server_config(
     service: &Service,
     req: StopServerConfigRequest,
 ) -> Result<Response<StopServerConfigResponse>, Status> {
-    let result = service::process::stop_server_config(&service.process_manager, req).await;
+    let result = service::process::stop_server_config(
+        &mut Context::PoolRef(&service.db),
+        &service.process_manager,
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn list_running_servers(
+    service: &Service,
+    req: ListRunningServersRequest,
+) -> Result<Response<ListRunningServersResponse>, Status> {
+    let result = service::process::list_running_servers(&service.process_manager, req).await;
 
     match result {
         Ok(response) => Ok(Response::new(response)),
@@ -33,11 +53,28 @@ pub async fn stop_server_config(
     }
 }
 
-pub async fn get_current_server_config(
+pub async fn stream_server_console(
     service: &Service,
-    req: GetCurrentServerConfigRequest,
-) -> Result<Response<GetCurrentServerConfigResponse>, Status> {
-    let result = service::process::get_current_server_config(&service.process_manager, req).await;
+    req: StreamServerConsoleRequest,
+) -> Result<Response<StreamServerConsoleStream>, Status> {
+    let result = service::process::stream_server_console(&service.process_manager, req).await;
+
+    match result {
+        Ok(stream) => Ok(Response::new(stream.map(Ok).boxed())),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
+pub async fn send_server_command(
+    service: &Service,
+    req: SendServerCommandRequest,
+) -> Result<Response<SendServerCommandResponse>, Status> {
+    let result = service::process::send_server_command(
+        &mut Context::PoolRef(&service.db),
+        &service.process_manager,
+        req,
+    )
+    .await;
 
     match result {
         Ok(response) => Ok(Response::new(response)),
@@ -1,6 +1,7 @@
 use common::tonic_idl_gen::{
     CreateServerConfigRequest, CreateServerConfigResponse, DeleteServerConfigRequest,
     DeleteServerConfigResponse, ListServerConfigRequest, ListServerConfigResponse,
+    UpdateServerConfigRequest, UpdateServerConfigResponse,
 };
 use server_common::db::context::Context;
 use tonic::{Response, Status};
@@ -37,6 +38,24 @@ pub async fn list_server_config(
     }
 }
 
+pub async fn update_server_config(
+    service: &Service,
+    req: UpdateServerConfigRequest,
+) -> Result<Response<UpdateServerConfigResponse>, Status> {
+    let result = service::server_config::update_server_config(
+        &mut Context::PoolRef(&service.db),
+        service.oss_client.with_http(&service.http_client),
+        &service.process_manager,
+        req,
+    )
+    .await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
+
 pub async fn delete_server_config(
     service: &Service,
     req: DeleteServerConfigRequest,
@@ -0,0 +1,18 @@
+use common::tonic_idl_gen::*;
+use server_common::db::context::Context;
+use tonic::{Response, Status};
+
+use crate::{service, Service};
+
+pub async fn list_server_runs(
+    service: &Service,
+    req: ListServerRunsRequest,
+) -> Result<Response<ListServerRunsResponse>, Status> {
+    let result =
+        service::server_run::list_server_runs(&mut Context::PoolRef(&service.db), req).await;
+
+    match result {
+        Ok(response) => Ok(Response::new(response)),
+        Err(err) => Err(Status::internal(err.to_string())),
+    }
+}
@@ -8,19 +8,30 @@ use common::tonic_idl_gen::mc_service_server::McServiceServer;
 use process::manager::Manager;
 use reqwest::Client;
 use server_common::{
-    db::pool::{create_pool_with, Config},
+    db::{
+        context::Context,
+        pool::{create_pool_with, Config},
+    },
     external_api::aliyun::oss::OssClient,
 };
 use service::process::ProcessService;
 use sqlx::{MySql, Pool};
+use tokio::signal::unix::{signal, SignalKind};
 use tonic::transport::Server;
-use tracing::info;
+use tracing::{info, warn};
 
 pub mod dao;
 pub mod handler;
 pub mod process;
 pub mod service;
 
+// 收到SIGTERM后等待服务器保存并退出的最长时间，需要小于k8s的terminationGracePeriodSeconds
+const SHUTDOWN_GRACE_PERIOD_ENV: &str = "RUSTWEB_MC_SHUTDOWN_GRACE_SECS";
+const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(90);
+// 服务器启动后等待就绪的最长时间，升级存档或加载大型整合包时需要调大
+const READY_TIMEOUT_ENV: &str = "RUSTWEB_MC_READY_TIMEOUT_SECS";
+const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(600);
+
 pub struct Service {
     pub db: Pool<MySql>,
     pub oss_client: OssClient,
@@ -33,15 +44,50 @@ async fn main() {
     tracing_subscriber::fmt::init();
 
     let service = Service::new().await.expect("initialize service failed");
+    let process_manager = service.process_manager.clone();
+    let grace_period = duration_from_env(SHUTDOWN_GRACE_PERIOD_ENV, DEFAULT_SHUTDOWN_GRACE_PERIOD);
 
     info!("starting service...");
     Server::builder()
         .add_service(McServiceServer::new(service))
-        .serve(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 13000))
+        .serve_with_shutdown(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 13000),
+            shutdown_signal(process_manager, grace_period),
+        )
         .await
         .expect("failed to start server");
 
-    unreachable!("service exited unexpectedly");
+    info!("service exited");
+}
+
+// tonic在此future结束后才开始等待已有的请求结束，控制台流在服务器停止前不会结束，
+// 所以需要先停止服务器
+async fn shutdown_signal(process_manager: Manager, grace_period: Duration) {
+    let mut terminate = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+
+    tokio::select! {
+        _ = terminate.recv() => info!("received SIGTERM"),
+        _ = tokio::signal::ctrl_c() => info!("received SIGINT"),
+    }
+
+    // 子进程在退出时会被强制结束，需要先正常停止，避免丢失未保存的区块
+    info!("stopping running servers, grace period: {grace_period:?}");
+    process_manager.shutdown(grace_period).await;
+}
+
+// 环境变量的单位为秒
+fn duration_from_env(name: &str, default: Duration) -> Duration {
+    let Ok(value) = std::env::var(name) else {
+        return default;
+    };
+
+    match value.parse() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => {
+            warn!("invalid {name}: {value}, using default");
+            default
+        }
+    }
 }
 
 impl Service {
@@ -54,7 +100,13 @@ impl Service {
             .build()?;
         let process_service =
             ProcessService::new(db.clone(), oss_client.clone(), http_client.clone());
-        let process_manager = Manager::new(process_service);
+        let process_manager = Manager::new(
+            process_service,
+            duration_from_env(READY_TIMEOUT_ENV, DEFAULT_READY_TIMEOUT),
+        );
+        service::server_run::interrupt_running_server_runs(&mut Context::PoolRef(&db)).await?;
+        service::process::resume_server_configs(&mut Context::PoolRef(&db), &process_manager)
+            .await?;
 
         Ok(Self {
             db,
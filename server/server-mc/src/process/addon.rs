@@ -0,0 +1,106 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use tokio::fs;
+use tracing::info;
+
+use crate::dao::server_config::{ServerConfig, ServerFlavor};
+
+use super::callback::ProcessService;
+
+const ADDON_EXTENSION: &str = "jar";
+// records the source of every installed jar, keyed by file name
+const ADDON_MANIFEST: &str = ".addons.json";
+
+fn addon_dir_name(flavor: ServerFlavor) -> Option<&'static str> {
+    match flavor {
+        ServerFlavor::Vanilla => None,
+        ServerFlavor::Paper => Some("plugins"),
+        ServerFlavor::Fabric => Some("mods"),
+    }
+}
+
+// the addon folder mirrors the config, jars that are no longer attached are removed
+pub(super) async fn install_addons(
+    service: &impl ProcessService,
+    server_config: &ServerConfig,
+    server_dir: &str,
+) -> Result<()> {
+    let Some(dir_name) = addon_dir_name(server_config.flavor) else {
+        return Ok(());
+    };
+    let addon_dir = Path::new(server_dir).join(dir_name);
+    fs::create_dir_all(&addon_dir).await?;
+
+    let addons: HashMap<String, String> = service
+        .list_addons(server_config.id)
+        .await?
+        .into_iter()
+        .map(|addon| (addon.name, addon.uri))
+        .collect();
+    let mut installed = read_manifest(&addon_dir).await;
+
+    // plugins keep their data in sub folders, only jars are managed
+    let mut entries = fs::read_dir(&addon_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !entry.file_type().await?.is_file()
+            || path.extension().is_none_or(|ext| ext != ADDON_EXTENSION)
+        {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if addons
+            .get(&name)
+            .is_none_or(|uri| installed.get(&name) != Some(uri))
+        {
+            info!("[{}] removing addon {}", server_config.id, path.display());
+            remove_file(&path).await?;
+            installed.remove(&name);
+        }
+    }
+
+    for (name, uri) in &addons {
+        let path = addon_dir.join(name);
+        if path.exists() {
+            continue;
+        }
+        info!("[{}] installing addon {name}", server_config.id);
+        service.download_addon(uri, &path.to_string_lossy()).await?;
+        installed.insert(name.clone(), uri.clone());
+        write_manifest(&addon_dir, &installed).await?;
+    }
+    installed.retain(|name, _| addons.contains_key(name));
+    write_manifest(&addon_dir, &installed).await?;
+
+    Ok(())
+}
+
+fn manifest_path(addon_dir: &Path) -> PathBuf {
+    addon_dir.join(ADDON_MANIFEST)
+}
+
+// a missing or broken manifest makes every jar look stale, so they are downloaded again
+async fn read_manifest(addon_dir: &Path) -> HashMap<String, String> {
+    match fs::read(manifest_path(addon_dir)).await {
+        Ok(content) => serde_json::from_slice(&content).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+async fn write_manifest(addon_dir: &Path, installed: &HashMap<String, String>) -> Result<()> {
+    fs::write(manifest_path(addon_dir), serde_json::to_vec(installed)?).await?;
+    Ok(())
+}
+
+async fn remove_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    fs::File as StdFile,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use tokio::{sync::Mutex, task::JoinHandle};
+use tracing::{info, warn};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use super::{callback::ProcessService, instance::ServerInstance, rcon::RconSession};
+
+pub(super) const BACKUP_INTERVAL: Duration = Duration::from_secs(3600);
+const SAVED_MESSAGE: &str = "Saved the game";
+// bukkit based servers keep the other dimensions in `world_nether/DIM-1` and `world_the_end/DIM1`,
+// they are zipped in the vanilla layout and moved out again by the server on the next start
+const DIMENSION_DIRS: [(&str, &str); 2] = [("_nether", "DIM-1"), ("_the_end", "DIM1")];
+
+// scheduled backup of a running server, autosave is paused while the world is zipped.
+// commands go to rcon directly, the instance loop stops reading messages once it is stopping
+pub(super) async fn backup_running_instance(
+    service: Arc<impl ProcessService>,
+    instance: Arc<ServerInstance>,
+    rcon: Arc<Mutex<RconSession>>,
+) {
+    let zip_path = backup_zip_path(&instance);
+    let result = async {
+        snapshot_running_world(&instance, &rcon, &zip_path).await?;
+        service
+            .backup_world(&instance.server_config, &zip_path)
+            .await
+    }
+    .await;
+    let _ = tokio::fs::remove_file(&zip_path).await;
+
+    match result {
+        Ok(()) => info!("[{}] world backup finished", instance.server_config.id),
+        Err(e) => warn!("[{}] world backup failed: {e:?}", instance.server_config.id),
+    }
+}
+
+// backup after the process exited, the world on disk is already consistent.
+// a scheduled backup still running fails its rcon commands now and is awaited first
+pub(super) async fn backup_stopped_instance(
+    service: &impl ProcessService,
+    instance: &ServerInstance,
+    scheduled_backup: Option<JoinHandle<()>>,
+) -> Result<()> {
+    if let Some(scheduled_backup) = scheduled_backup {
+        let _ = scheduled_backup.await;
+    }
+
+    let world_dir = instance.world_dir();
+    if !Path::new(&world_dir).exists() {
+        return Ok(());
+    }
+
+    let zip_path = backup_zip_path(instance);
+    let result = async {
+        zip_world(world_dir, zip_path.clone()).await?;
+        service
+            .backup_world(&instance.server_config, &zip_path)
+            .await
+    }
+    .await;
+    let _ = tokio::fs::remove_file(&zip_path).await;
+
+    result
+}
+
+async fn snapshot_running_world(
+    instance: &ServerInstance,
+    rcon: &Mutex<RconSession>,
+    zip_path: &str,
+) -> Result<()> {
+    rcon.lock().await.command("save-off").await?;
+    let result = async {
+        // rcon answers once the world is written
+        let output = rcon.lock().await.command("save-all flush").await?;
+        if !output.contains(SAVED_MESSAGE) {
+            bail!("world save failed: {output}");
+        }
+        zip_world(instance.world_dir(), zip_path.to_owned()).await
+    }
+    .await;
+
+    // always turn autosave back on, even if the snapshot failed
+    if let Err(e) = rcon.lock().await.command("save-on").await {
+        warn!(
+            "[{}] failed to enable autosave: {e}",
+            instance.server_config.id
+        );
+    }
+
+    result
+}
+
+fn backup_zip_path(instance: &ServerInstance) -> String {
+    format!(
+        "{}/backup-{}.zip",
+        instance.server_dir(),
+        Utc::now().timestamp_millis()
+    )
+}
+
+// entries are relative to the world folder, the same layout `download_world` unpacks
+async fn zip_world(world_dir: String, to_path: String) -> Result<()> {
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut writer = ZipWriter::new(StdFile::create(&to_path)?);
+        let options = SimpleFileOptions::default();
+
+        // (folder on disk, its path in the zip)
+        let mut dirs = DIMENSION_DIRS
+            .iter()
+            .map(|(suffix, dimension)| {
+                (
+                    Path::new(&format!("{world_dir}{suffix}")).join(dimension),
+                    PathBuf::from(dimension),
+                )
+            })
+            .filter(|(path, _)| path.is_dir())
+            .collect::<Vec<_>>();
+        // a stale vanilla dimension folder in the world is replaced by the one the server uses
+        let replaced = dirs
+            .iter()
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>();
+        for (_, name) in &dirs {
+            writer.add_directory(name.to_string_lossy(), options)?;
+        }
+        dirs.push((PathBuf::from(&world_dir), PathBuf::new()));
+
+        while let Some((dir, dir_name)) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let name = dir_name.join(path.file_name().ok_or(anyhow!("invalid path"))?);
+                if replaced.contains(&name) {
+                    continue;
+                }
+
+                if path.is_dir() {
+                    writer.add_directory(name.to_string_lossy(), options)?;
+                    dirs.push((path, name));
+                } else {
+                    writer.start_file(name.to_string_lossy(), options)?;
+                    std::io::copy(&mut StdFile::open(&path)?, &mut writer)?;
+                }
+            }
+        }
+
+        writer.finish()?;
+        Ok(())
+    })
+    .await?
+}
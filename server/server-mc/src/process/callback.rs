@@ -1,24 +1,93 @@
 use std::future::Future;
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use tokio_util::sync::CancellationToken;
 
-use crate::dao::server_config::ServerConfig;
+use crate::dao::{
+    addon::ServerConfigAddon,
+    server_config::{ServerConfig, ServerFlavor},
+};
+
+use super::{
+    rcon::RconConfig,
+    status::{ProgressCounter, RunRecord},
+};
 
 pub trait ProcessService: Send + Sync + 'static {
+    fn java_major_version(&self, version: &str) -> impl Future<Output = Result<u32>> + Send;
     fn download_server_jar(
         &self,
+        flavor: ServerFlavor,
+        version: &str,
+        to_path: &str,
+        progress: &ProgressCounter,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn verify_server_jar(
+        &self,
+        flavor: ServerFlavor,
         version: &str,
+        path: &str,
+    ) -> impl Future<Output = Result<bool>> + Send;
+    fn download_world(
+        &self,
+        uri: &str,
         to_path: &str,
+        progress: &ProgressCounter,
+        // checked while unzipping, which keeps running when the download future is dropped
+        cancel: &CancellationToken,
     ) -> impl Future<Output = Result<()>> + Send;
-    fn download_world(&self, uri: &str, to_path: &str) -> impl Future<Output = Result<()>> + Send;
+    fn list_addons(
+        &self,
+        server_config_id: u64,
+    ) -> impl Future<Output = Result<Vec<ServerConfigAddon>>> + Send;
+    fn download_addon(&self, uri: &str, to_path: &str) -> impl Future<Output = Result<()>> + Send;
+    // returns the sha1 of the pack
+    fn download_resource_pack(
+        &self,
+        uri: &str,
+        to_dir: &str,
+    ) -> impl Future<Output = Result<String>> + Send;
     fn initialize_config_files(
         &self,
         root: &str,
         world_dir_name: &str,
         server_config: &ServerConfig,
+        port: u16,
+        rcon: &RconConfig,
+        resource_pack_sha1: Option<&str>,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn backup_world(
+        &self,
+        server_config: &ServerConfig,
+        zip_path: &str,
     ) -> impl Future<Output = Result<()>> + Send;
 
-    fn server_started(&self) -> impl Future<Output = ()> + Send;
-    fn stdout_line(&self, line: &str) -> impl Future<Output = ()> + Send;
-    fn server_stop(&self) -> impl Future<Output = ()> + Send;
+    fn player_joined(
+        &self,
+        server_config: &ServerConfig,
+        name: &str,
+        uuid: &str,
+    ) -> impl Future<Output = Result<u64>> + Send;
+    fn player_left(&self, session_id: u64) -> impl Future<Output = Result<()>> + Send;
+
+    fn run_started(
+        &self,
+        server_config: &ServerConfig,
+        start_time: DateTime<Utc>,
+    ) -> impl Future<Output = Result<u64>> + Send;
+    fn record_run(
+        &self,
+        server_config: &ServerConfig,
+        run_id: Option<u64>,
+        record: &RunRecord,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn server_started(&self, server_config: &ServerConfig) -> impl Future<Output = ()> + Send;
+    fn stdout_line(
+        &self,
+        server_config: &ServerConfig,
+        line: &str,
+    ) -> impl Future<Output = ()> + Send;
+    fn server_stop(&self, server_config: &ServerConfig) -> impl Future<Output = ()> + Send;
 }
@@ -1,6 +1,35 @@
+use anyhow::Result;
+use tokio::sync::oneshot;
+
 use crate::dao::server_config::ServerConfig;
 
 pub(super) enum Message {
-    StartServerConfig(ServerConfig),
-    StopServerConfig,
+    StartServerConfig {
+        server_config: Box<ServerConfig>,
+        result: oneshot::Sender<Result<()>>,
+    },
+    StopServerConfig(u64),
+    Command {
+        id: u64,
+        command: String,
+        result: oneshot::Sender<Result<String>>,
+    },
+}
+
+// the server shuts itself down on these, the exit is not a crash
+pub fn is_stop_command(command: &str) -> bool {
+    let command = command.trim_start();
+    let command = command.strip_prefix('/').unwrap_or(command);
+    command
+        .split_whitespace()
+        .next()
+        .is_some_and(|name| name.eq_ignore_ascii_case("stop"))
+}
+
+pub(super) enum InstanceMessage {
+    Stop,
+    Command {
+        command: String,
+        result: oneshot::Sender<Result<String>>,
+    },
 }
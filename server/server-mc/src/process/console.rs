@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::{
+    broadcast::{self, Receiver, Sender},
+    RwLock,
+};
+
+const CONSOLE_HISTORY_SIZE: usize = 500;
+const CONSOLE_CHANNEL_SIZE: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct ConsoleLine {
+    pub time: DateTime<Utc>,
+    pub line: String,
+}
+
+pub(super) struct Console {
+    history: RwLock<VecDeque<ConsoleLine>>,
+    // dropped on close, so subscribers see the end of the stream
+    sender: RwLock<Option<Sender<ConsoleLine>>>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CONSOLE_CHANNEL_SIZE);
+
+        Self {
+            history: RwLock::new(VecDeque::with_capacity(CONSOLE_HISTORY_SIZE)),
+            sender: RwLock::new(Some(sender)),
+        }
+    }
+
+    pub async fn push(&self, line: &str) {
+        let line = ConsoleLine {
+            time: Utc::now(),
+            line: line.to_owned(),
+        };
+
+        let mut history = self.history.write().await;
+        if history.len() >= CONSOLE_HISTORY_SIZE {
+            history.pop_front();
+        }
+        history.push_back(line.clone());
+
+        // sending fails when nobody is subscribed, which is fine
+        if let Some(sender) = self.sender.read().await.as_ref() {
+            let _ = sender.send(line);
+        }
+    }
+
+    // called when the instance loop exits, the history stays readable
+    pub async fn close(&self) {
+        self.sender.write().await.take();
+    }
+
+    pub async fn recent_lines(&self, count: usize) -> Vec<String> {
+        let history = self.history.read().await;
+
+        history
+            .iter()
+            .skip(history.len().saturating_sub(count))
+            .map(|line| line.line.clone())
+            .collect()
+    }
+
+    // snapshot and subscribe under the same lock, so no line is lost or duplicated in between
+    pub async fn subscribe(&self) -> (Vec<ConsoleLine>, Receiver<ConsoleLine>) {
+        let history = self.history.read().await;
+        let receiver = match self.sender.read().await.as_ref() {
+            Some(sender) => sender.subscribe(),
+            // a receiver without sender ends right after the history
+            None => broadcast::channel(1).1,
+        };
+
+        (history.iter().cloned().collect(), receiver)
+    }
+}
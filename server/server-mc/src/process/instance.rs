@@ -0,0 +1,764 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    path::Path,
+    process::ExitStatus,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use tokio::{
+    sync::{
+        mpsc::{Receiver, Sender},
+        Mutex, RwLock,
+    },
+    task::JoinHandle,
+    time::{timeout, Instant},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::dao::server_config::{RestartPolicy, ServerConfig};
+
+use super::{
+    addon::install_addons,
+    backup,
+    callback::ProcessService,
+    communicate::{is_stop_command, InstanceMessage},
+    console::Console,
+    java::select_java_runtime,
+    jvm::jvm_arguments,
+    lifecycle::ProcessLifeCycle,
+    log::{parse_log_line, LogEntry},
+    ping::{self, ServerStatus},
+    player::{self, PlayerTracker},
+    rcon::{RconConfig, RconSession},
+    status::{ProcessStatus, ProgressCounter, RunRecord, RunResult, StartingStatus, StatusInfo},
+    RUN_DIR, SERVER_JAR_DIR,
+};
+
+const WORLD_DIR_NAME: &str = "world";
+const RESOURCE_PACK_DIR_NAME: &str = "resource-pack";
+
+// restart delay doubles after each consecutive crash
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(5);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(300);
+// a run lasting this long resets the consecutive crash counter
+const RESTART_RESET_AFTER: Duration = Duration::from_secs(600);
+// output attached to the error when the process dies
+const ERROR_OUTPUT_LINES: usize = 20;
+
+enum InstanceExit {
+    Stopped,
+    // the process exited on its own with code 0
+    Exited(ExitStatus),
+    Crashed(ExitStatus),
+    Cancelled,
+}
+
+// everything the stages before launching prepared
+struct LaunchConfig {
+    java_path: String,
+    jar_path: String,
+    server_dir: String,
+    rcon: RconConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct InstanceInfo {
+    pub server_config: ServerConfig,
+    pub port: u16,
+    pub status: HashMap<ProcessStatus, StatusInfo>,
+    pub server_status: Option<ServerStatus>,
+    pub resource_pack_sha1: Option<String>,
+}
+
+pub(super) struct ServerInstance {
+    pub server_config: ServerConfig,
+    pub port: u16,
+    pub message_sender: Sender<InstanceMessage>,
+    // the process is killed when it does not print 'Done' in time
+    ready_timeout: Duration,
+    pub console: Console,
+    // the instance loop does not read messages while starting, a stop cancels the start instead
+    start_cancel: CancellationToken,
+    status: RwLock<HashMap<ProcessStatus, StatusInfo>>,
+    // progress of the open stage, moved into its status info when the stage ends
+    progress: ProgressCounter,
+    server_status: RwLock<Option<ServerStatus>>,
+    // server-api only serves the pack the running server announced
+    resource_pack_sha1: RwLock<Option<String>>,
+    pub players: RwLock<PlayerTracker>,
+}
+
+impl ServerInstance {
+    pub fn new(
+        server_config: ServerConfig,
+        port: u16,
+        message_sender: Sender<InstanceMessage>,
+        ready_timeout: Duration,
+    ) -> Self {
+        Self {
+            server_config,
+            port,
+            message_sender,
+            ready_timeout,
+            console: Console::new(),
+            start_cancel: CancellationToken::new(),
+            status: RwLock::new(HashMap::new()),
+            progress: ProgressCounter::default(),
+            server_status: RwLock::new(None),
+            resource_pack_sha1: RwLock::new(None),
+            players: RwLock::new(PlayerTracker::default()),
+        }
+    }
+
+    // the instance loop owns the receiver, so a closed channel means the process is gone
+    pub fn is_alive(&self) -> bool {
+        !self.message_sender.is_closed()
+    }
+
+    pub async fn info(&self) -> InstanceInfo {
+        InstanceInfo {
+            server_config: self.server_config.clone(),
+            port: self.port,
+            status: self
+                .status
+                .read()
+                .await
+                .iter()
+                .map(|(status, info)| {
+                    let mut info = info.clone();
+                    if info.end_time.is_none() {
+                        info.progress = info.progress.or_else(|| self.progress.snapshot());
+                    }
+                    (*status, info)
+                })
+                .collect(),
+            server_status: self.server_status.read().await.clone(),
+            resource_pack_sha1: self.resource_pack_sha1.read().await.clone(),
+        }
+    }
+
+    pub fn cancel_start(&self) {
+        self.start_cancel.cancel();
+    }
+
+    pub async fn set_server_status(&self, server_status: Option<ServerStatus>) {
+        *self.server_status.write().await = server_status;
+    }
+
+    pub fn server_dir(&self) -> String {
+        server_dir(&self.server_config)
+    }
+
+    pub fn world_dir(&self) -> String {
+        format!("{}/{WORLD_DIR_NAME}", self.server_dir())
+    }
+
+    async fn start_status(&self, status: ProcessStatus) {
+        info!("[{}] enter status: {:?}", self.server_config.id, status);
+        let mut map = self.status.write().await;
+        map.iter_mut()
+            .filter(|(_, value)| value.end_time.is_none())
+            .for_each(|(_, value)| {
+                value.end_time = Some(Utc::now());
+                value.progress = value.progress.or_else(|| self.progress.snapshot());
+            });
+        self.progress.reset();
+        map.insert(
+            status,
+            StatusInfo {
+                start_time: Utc::now(),
+                ..Default::default()
+            },
+        );
+    }
+
+    async fn set_exit_code(&self, status: ProcessStatus, exit_code: Option<i32>) {
+        if let Some(info) = self.status.write().await.get_mut(&status) {
+            info.exit_code = exit_code;
+        }
+    }
+
+    // the timeline of the crashed run is replaced by the crash record
+    async fn crashed(&self, crashes: u32, exit_status: &ExitStatus) {
+        warn!(
+            "[{}] process exited unexpectedly: {exit_status}",
+            self.server_config.id
+        );
+        let error = self.exit_error(exit_status).await;
+        let mut map = self.status.write().await;
+        map.retain(|status, _| matches!(status, ProcessStatus::Crashed(_)));
+        map.insert(
+            ProcessStatus::Crashed(crashes),
+            StatusInfo {
+                start_time: Utc::now(),
+                error: Some(error),
+                exit_code: exit_status.code(),
+                ..Default::default()
+            },
+        );
+    }
+
+    // stages of the run started at start_time, crash records of earlier runs are left out
+    async fn run_record(
+        &self,
+        start_time: DateTime<Utc>,
+        result: RunResult,
+        exit_status: Option<&ExitStatus>,
+    ) -> RunRecord {
+        let stop_time = Utc::now();
+        let mut status = self
+            .status
+            .read()
+            .await
+            .iter()
+            .filter(|(status, info)| {
+                !matches!(status, ProcessStatus::Crashed(_)) && info.start_time >= start_time
+            })
+            .map(|(status, info)| {
+                let mut info = info.clone();
+                info.end_time = info.end_time.or(Some(stop_time));
+                (*status, info)
+            })
+            .collect::<Vec<_>>();
+        status.sort_by_key(|(status, info)| (info.start_time, *status));
+
+        let error = match exit_status {
+            Some(exit_status) => Some(self.exit_error(exit_status).await),
+            None => status.iter().find_map(|(_, info)| info.error.clone()),
+        };
+        let exit_code = exit_status
+            .and_then(ExitStatus::code)
+            .or_else(|| status.iter().find_map(|(_, info)| info.exit_code));
+
+        RunRecord {
+            start_time,
+            stop_time,
+            result,
+            error,
+            exit_code,
+            status,
+        }
+    }
+
+    async fn exit_error(&self, exit_status: &ExitStatus) -> String {
+        with_recent_output(
+            format!("process exited with {exit_status}"),
+            &self.console.recent_lines(ERROR_OUTPUT_LINES).await,
+        )
+    }
+
+    async fn status_error(&self, error: impl Debug + Display) {
+        warn!("[{}] status error: {:?}", self.server_config.id, error);
+        self.status.write().await.iter_mut().for_each(|(_, value)| {
+            if value.end_time.is_none() {
+                value.end_time = Some(Utc::now());
+                value.error = Some(error.to_string());
+                value.progress = value.progress.or_else(|| self.progress.snapshot());
+            }
+        });
+    }
+}
+
+pub(super) fn server_dir(server_config: &ServerConfig) -> String {
+    format!("{}/{}", RUN_DIR, server_config.id)
+}
+
+pub(super) async fn instance_loop(
+    service: Arc<impl ProcessService>,
+    instance: Arc<ServerInstance>,
+    mut receiver: Receiver<InstanceMessage>,
+) {
+    let server_config = &instance.server_config;
+    let mut crashes = 0;
+    let mut retries = 0;
+
+    loop {
+        let run_time = Instant::now();
+        let start_time = Utc::now();
+        let run_id = start_run(service.as_ref(), &instance, start_time).await;
+        let exit = run_instance(&service, &instance, &mut receiver).await;
+        instance.set_server_status(None).await;
+        instance.players.write().await.clear();
+        let exit = match exit {
+            Ok(exit) => exit,
+            Err(e) => {
+                // a failed run may have had players online, their sessions are finished here too
+                service.server_stop(server_config).await;
+                instance.status_error(e).await;
+                let record = instance
+                    .run_record(start_time, RunResult::Failed, None)
+                    .await;
+                record_run(service.as_ref(), &instance, run_id, &record).await;
+                instance.console.close().await;
+                return;
+            }
+        };
+        service.server_stop(server_config).await;
+
+        let record = match &exit {
+            InstanceExit::Stopped => {
+                instance
+                    .run_record(start_time, RunResult::Stopped, None)
+                    .await
+            }
+            InstanceExit::Cancelled => {
+                instance
+                    .run_record(start_time, RunResult::Cancelled, None)
+                    .await
+            }
+            InstanceExit::Exited(exit_status) => RunRecord {
+                exit_code: exit_status.code(),
+                ..instance
+                    .run_record(start_time, RunResult::Stopped, None)
+                    .await
+            },
+            InstanceExit::Crashed(exit_status) => {
+                instance
+                    .run_record(start_time, RunResult::Crashed, Some(exit_status))
+                    .await
+            }
+        };
+        record_run(service.as_ref(), &instance, run_id, &record).await;
+
+        let backoff = match exit {
+            InstanceExit::Crashed(exit_status) => {
+                if run_time.elapsed() >= RESTART_RESET_AFTER {
+                    retries = 0;
+                }
+                crashes += 1;
+                retries += 1;
+                instance.crashed(crashes, &exit_status).await;
+
+                if !should_restart(server_config, &exit_status, retries) {
+                    break;
+                }
+
+                RESTART_BACKOFF_BASE
+                    .saturating_mul(2u32.saturating_pow(retries - 1))
+                    .min(RESTART_BACKOFF_MAX)
+            }
+            // a clean exit is only restarted by the always policy and does not count as a retry
+            InstanceExit::Exited(_) if server_config.restart_policy == RestartPolicy::Always => {
+                retries = 0;
+                RESTART_BACKOFF_BASE
+            }
+            InstanceExit::Stopped | InstanceExit::Exited(_) | InstanceExit::Cancelled => break,
+        };
+        info!(
+            "[{}] restarting in {backoff:?}, retry {retries}",
+            server_config.id
+        );
+        if !wait_restart_backoff(backoff, &mut receiver).await {
+            break;
+        }
+    }
+
+    instance.start_status(ProcessStatus::Terminated).await;
+    instance.console.close().await;
+}
+
+// the run is saved before starting so it survives the pod being killed
+async fn start_run(
+    service: &impl ProcessService,
+    instance: &ServerInstance,
+    start_time: DateTime<Utc>,
+) -> Option<u64> {
+    service
+        .run_started(&instance.server_config, start_time)
+        .await
+        .inspect_err(|e| {
+            warn!(
+                "[{}] failed to save run start: {e:?}",
+                instance.server_config.id
+            )
+        })
+        .ok()
+}
+
+async fn record_run(
+    service: &impl ProcessService,
+    instance: &ServerInstance,
+    run_id: Option<u64>,
+    record: &RunRecord,
+) {
+    if let Err(e) = service
+        .record_run(&instance.server_config, run_id, record)
+        .await
+    {
+        warn!(
+            "[{}] failed to record run: {e:?}",
+            instance.server_config.id
+        );
+    }
+}
+
+async fn run_instance(
+    service: &Arc<impl ProcessService>,
+    instance: &Arc<ServerInstance>,
+    receiver: &mut Receiver<InstanceMessage>,
+) -> Result<InstanceExit> {
+    let server_config = &instance.server_config;
+
+    let Some((mut process_lifecycle, rcon)) = starting_service(service.as_ref(), instance).await?
+    else {
+        info!("[{}] start cancelled", server_config.id);
+        instance.start_status(ProcessStatus::Cancelled).await;
+        return Ok(InstanceExit::Cancelled);
+    };
+    // commands may wait on the server thread, stdout must keep draining meanwhile
+    let rcon = Arc::new(Mutex::new(rcon));
+
+    service.server_started(server_config).await;
+    instance.start_status(ProcessStatus::Running).await;
+
+    let mut backup_interval = tokio::time::interval_at(
+        Instant::now() + backup::BACKUP_INTERVAL,
+        backup::BACKUP_INTERVAL,
+    );
+    let mut ping_interval = tokio::time::interval(ping::PING_INTERVAL);
+    // set when the operator sends 'stop', the following exit is not restarted
+    let mut stop_requested = false;
+    let mut scheduled_backup: Option<JoinHandle<()>> = None;
+
+    loop {
+        tokio::select! {
+            _ = backup_interval.tick() => {
+                if scheduled_backup.as_ref().is_some_and(|task| !task.is_finished()) {
+                    warn!("[{}] previous backup is still running, skipped", server_config.id);
+                    continue;
+                }
+                scheduled_backup = Some(tokio::spawn(backup::backup_running_instance(
+                    service.clone(),
+                    instance.clone(),
+                    rcon.clone(),
+                )));
+            },
+            _ = ping_interval.tick() => {
+                tokio::spawn(ping::ping_running_instance(instance.clone()));
+            },
+            message = receiver.recv() => {
+                let Some(message) = message else {
+                    break;
+                };
+
+                match message {
+                    InstanceMessage::Stop => break,
+                    InstanceMessage::Command { command, result } => {
+                        info!("[{}] sending command: {command}", server_config.id);
+                        stop_requested |= is_stop_command(&command);
+                        let rcon = rcon.clone();
+                        tokio::spawn(async move {
+                            let _ = result.send(rcon.lock().await.command(&command).await);
+                        });
+                    }
+                }
+            },
+            line = process_lifecycle.read_line() => {
+                match line {
+                    Ok(Some(line)) => {
+                        instance.console.push(&line).await;
+                        if let Some(entry) = parse_log_line(&line).entry() {
+                            player::handle_line(service, instance, entry).await;
+                        }
+                        service.stdout_line(server_config, &line).await;
+                    },
+                    Ok(None) => {
+                        // stdout is closed, the process exited on its own
+                        let exit_status = process_lifecycle.wait().await?;
+                        info!("[{}] process exited with {exit_status}", server_config.id);
+                        if stop_requested {
+                            instance.start_status(ProcessStatus::Terminating).await;
+                            instance
+                                .set_exit_code(ProcessStatus::Terminating, exit_status.code())
+                                .await;
+                            if let Err(e) = backup::backup_stopped_instance(
+                                service.as_ref(),
+                                instance,
+                                scheduled_backup.take(),
+                            )
+                            .await
+                            {
+                                warn!("[{}] world backup failed: {e:?}", server_config.id);
+                            }
+                            return Ok(InstanceExit::Stopped);
+                        }
+                        if exit_status.success() {
+                            return Ok(InstanceExit::Exited(exit_status));
+                        }
+                        return Ok(InstanceExit::Crashed(exit_status));
+                    },
+                    Err(_) => {
+                        break;
+                    }
+                };
+            }
+        };
+    }
+
+    info!("[{}] stoping service", server_config.id);
+    instance.start_status(ProcessStatus::Terminating).await;
+    match process_lifecycle.stop_service().await {
+        Ok(exit_status) => {
+            info!("[{}] process exited with {exit_status}", server_config.id);
+            instance
+                .set_exit_code(ProcessStatus::Terminating, exit_status.code())
+                .await;
+        }
+        Err(e) => instance.status_error(e).await,
+    }
+
+    if let Err(e) =
+        backup::backup_stopped_instance(service.as_ref(), instance, scheduled_backup).await
+    {
+        warn!("[{}] world backup failed: {e:?}", server_config.id);
+    }
+
+    Ok(InstanceExit::Stopped)
+}
+
+fn should_restart(server_config: &ServerConfig, exit_status: &ExitStatus, retries: u32) -> bool {
+    match server_config.restart_policy {
+        RestartPolicy::Never => false,
+        RestartPolicy::OnFailure => {
+            !exit_status.success() && retries <= server_config.restart_max_retries
+        }
+        RestartPolicy::Always => true,
+    }
+}
+
+// returns false when the server is stopped while waiting
+async fn wait_restart_backoff(backoff: Duration, receiver: &mut Receiver<InstanceMessage>) -> bool {
+    let restart = tokio::time::sleep(backoff);
+    tokio::pin!(restart);
+
+    loop {
+        tokio::select! {
+            _ = &mut restart => return true,
+            message = receiver.recv() => match message {
+                None | Some(InstanceMessage::Stop) => return false,
+                Some(InstanceMessage::Command { result, .. }) => {
+                    let _ = result.send(Err(anyhow!("server is waiting to restart")));
+                }
+            },
+        }
+    }
+}
+
+// returns None when the start is cancelled by a stop
+async fn starting_service(
+    service: &impl ProcessService,
+    instance: &ServerInstance,
+) -> Result<Option<(ProcessLifeCycle, RconSession)>> {
+    let cancel = &instance.start_cancel;
+    // dropping an unfinished stage aborts its downloads
+    let launch = tokio::select! {
+        launch = prepare_service(service, instance) => launch?,
+        _ = cancel.cancelled() => return Ok(None),
+    };
+
+    // starting server and wait to ready
+    instance
+        .start_status(ProcessStatus::Starting(
+            StartingStatus::WaitingForServerReady,
+        ))
+        .await;
+    let jvm_args = jvm_arguments(&instance.server_config.jvm_options)?;
+    info!("jvm args: {jvm_args:?}");
+    let mut lifecycle = ProcessLifeCycle::start(
+        &launch.java_path,
+        &jvm_args,
+        &launch.jar_path,
+        &launch.server_dir,
+    )?;
+    let ready = tokio::select! {
+        ready = timeout(instance.ready_timeout, wait_for_ready(instance, &mut lifecycle)) => ready,
+        _ = cancel.cancelled() => {
+            // the half-started server has nothing worth saving
+            lifecycle.kill().await?;
+            return Ok(None);
+        }
+    };
+
+    let error = match ready {
+        Ok(Ok(())) => return Ok(Some((lifecycle, RconSession::new(launch.rcon)))),
+        Ok(Err(e)) => {
+            let exit_status = lifecycle.kill().await?;
+            instance
+                .set_exit_code(
+                    ProcessStatus::Starting(StartingStatus::WaitingForServerReady),
+                    exit_status.code(),
+                )
+                .await;
+            format!("{e}, process exited with {exit_status}")
+        }
+        Err(_) => {
+            // e.g. waiting on a prompt or a port already in use
+            lifecycle.kill().await?;
+            format!("server is not ready after {:?}", instance.ready_timeout)
+        }
+    };
+
+    bail!(with_recent_output(
+        error,
+        &instance.console.recent_lines(ERROR_OUTPUT_LINES).await,
+    ))
+}
+
+fn with_recent_output(error: String, lines: &[String]) -> String {
+    if lines.is_empty() {
+        return error;
+    }
+
+    format!("{error}\n{}", lines.join("\n"))
+}
+
+async fn prepare_service(
+    service: &impl ProcessService,
+    instance: &ServerInstance,
+) -> Result<LaunchConfig> {
+    let server_config = &instance.server_config;
+    let jar_path = format!(
+        "{}/{}/{}.jar",
+        SERVER_JAR_DIR, server_config.flavor, &server_config.mc_version
+    );
+    let server_dir = instance.server_dir();
+    let world_path = instance.world_dir();
+
+    if let Some(parent) = Path::new(&jar_path).parent() {
+        if !parent.exists() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    migrate_legacy_world_dir(&server_dir)?;
+    if !Path::new(&server_dir).exists() {
+        std::fs::create_dir_all(Path::new(&server_dir))?;
+    }
+    info!("starting service, jar_path: {jar_path}, world_path: {world_path}");
+
+    // select java runtime
+    instance
+        .start_status(ProcessStatus::Starting(StartingStatus::SelectJavaRuntime))
+        .await;
+    let java_major_version = service
+        .java_major_version(&server_config.mc_version)
+        .await?;
+    let java_path = select_java_runtime(java_major_version)?;
+    info!("selected java runtime {java_path} for java {java_major_version}");
+
+    // download jar
+    instance
+        .start_status(ProcessStatus::Starting(StartingStatus::DownloadServerJar))
+        .await;
+    // a cached jar that fails the checksum is downloaded again
+    let jar_valid = Path::new(&jar_path).exists()
+        && service
+            .verify_server_jar(server_config.flavor, &server_config.mc_version, &jar_path)
+            .await?;
+    if !jar_valid {
+        warn!("[{}] server jar is missing or corrupt", server_config.id);
+        service
+            .download_server_jar(
+                server_config.flavor,
+                &server_config.mc_version,
+                &jar_path,
+                &instance.progress,
+            )
+            .await?;
+    }
+
+    // download world
+    instance
+        .start_status(ProcessStatus::Starting(StartingStatus::DownloadWorld))
+        .await;
+    if !Path::new(&world_path).exists() {
+        if let Some(world_uri) = &server_config.world_uri {
+            service
+                .download_world(
+                    world_uri,
+                    &world_path,
+                    &instance.progress,
+                    &instance.start_cancel,
+                )
+                .await?;
+        }
+    }
+    // the pack is served by server-api, only its hash is needed here
+    let resource_pack_sha1 = match &server_config.resource_uri {
+        Some(resource_uri) => Some(
+            service
+                .download_resource_pack(
+                    resource_uri,
+                    &format!("{server_dir}/{RESOURCE_PACK_DIR_NAME}"),
+                )
+                .await?,
+        ),
+        None => None,
+    };
+    *instance.resource_pack_sha1.write().await = resource_pack_sha1.clone();
+
+    // install mods or plugins
+    instance
+        .start_status(ProcessStatus::Starting(StartingStatus::InstallAddons))
+        .await;
+    install_addons(service, server_config, &server_dir).await?;
+
+    // initialize configuration
+    instance
+        .start_status(ProcessStatus::Starting(
+            StartingStatus::InitializeConfigFile,
+        ))
+        .await;
+    // a new rcon password for every launch
+    let rcon = RconConfig::generate(instance.port);
+    service
+        .initialize_config_files(
+            &server_dir,
+            WORLD_DIR_NAME,
+            server_config,
+            instance.port,
+            &rcon,
+            resource_pack_sha1.as_deref(),
+        )
+        .await?;
+
+    Ok(LaunchConfig {
+        java_path,
+        jar_path,
+        server_dir,
+        rcon,
+    })
+}
+
+async fn wait_for_ready(instance: &ServerInstance, lifecycle: &mut ProcessLifeCycle) -> Result<()> {
+    // loop to detect 'Done' message
+    while let Some(message) = lifecycle.read_line().await? {
+        instance.console.push(&message).await;
+
+        let line = parse_log_line(&message);
+        if line.entry().is_some_and(LogEntry::is_ready) {
+            return Ok(());
+        }
+    }
+
+    bail!("process shutdown before getting ready")
+}
+
+// worlds used to be unpacked directly into RUN_DIR/{id}, move them into the server folder
+fn migrate_legacy_world_dir(server_dir: &str) -> Result<()> {
+    if !Path::new(server_dir).join("level.dat").exists() {
+        return Ok(());
+    }
+
+    info!("migrating legacy world dir: {server_dir}");
+    let legacy_dir = format!("{server_dir}.legacy");
+    std::fs::rename(server_dir, &legacy_dir)?;
+    std::fs::create_dir_all(server_dir)?;
+    std::fs::rename(legacy_dir, Path::new(server_dir).join(WORLD_DIR_NAME))?;
+
+    Ok(())
+}
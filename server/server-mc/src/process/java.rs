@@ -0,0 +1,45 @@
+use anyhow::{anyhow, bail, Result};
+use tracing::warn;
+
+// installed runtimes, e.g. `8=/usr/lib/jvm/java-8/bin/java,21=/usr/lib/jvm/java-21/bin/java`.
+// a version may point at a newer runtime to allow running on it, e.g. `16=/usr/lib/jvm/java-17/bin/java`
+const JAVA_RUNTIMES_ENV: &str = "RUSTWEB_MC_JAVA_RUNTIMES";
+const DEFAULT_JAVA: &str = "java";
+
+// only the runtime listed for the exact major version is used, a newer one must be configured explicitly
+pub(super) fn select_java_runtime(java_major_version: u32) -> Result<String> {
+    let Ok(runtimes) = std::env::var(JAVA_RUNTIMES_ENV) else {
+        return Ok(DEFAULT_JAVA.to_owned());
+    };
+    if java_major_version == 0 {
+        warn!("required java version is unknown, fallback to {DEFAULT_JAVA}");
+        return Ok(DEFAULT_JAVA.to_owned());
+    }
+
+    let runtimes = parse_java_runtimes(&runtimes)?;
+    runtimes
+        .into_iter()
+        .find(|(version, _)| *version == java_major_version)
+        .map(|(_, path)| path)
+        .ok_or(anyhow!(
+            "no java runtime available for java {java_major_version}"
+        ))
+}
+
+fn parse_java_runtimes(runtimes: &str) -> Result<Vec<(u32, String)>> {
+    runtimes
+        .split(',')
+        .map(str::trim)
+        .filter(|runtime| !runtime.is_empty())
+        .map(|runtime| {
+            let Some((version, path)) = runtime.split_once('=') else {
+                bail!("invalid {JAVA_RUNTIMES_ENV} entry: {runtime}");
+            };
+            let version = version
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid {JAVA_RUNTIMES_ENV} entry: {runtime}"))?;
+            Ok((version, path.trim().to_owned()))
+        })
+        .collect()
+}
@@ -0,0 +1,104 @@
+use anyhow::{anyhow, bail, Result};
+
+use crate::dao::server_config::{JvmOptions, ServerConfig};
+
+// upper bound of the heap a single server may request, in MB
+const MAX_MEMORY_ENV: &str = "RUSTWEB_MC_MAX_MEMORY_MB";
+// upper bound of the heaps of all running servers together, in MB
+const TOTAL_MEMORY_ENV: &str = "RUSTWEB_MC_TOTAL_MEMORY_MB";
+
+// https://docs.papermc.io/paper/aikars-flags
+const AIKAR_FLAGS: &[&str] = &[
+    "-XX:+UseG1GC",
+    "-XX:+ParallelRefProcEnabled",
+    "-XX:MaxGCPauseMillis=200",
+    "-XX:+UnlockExperimentalVMOptions",
+    "-XX:+DisableExplicitGC",
+    "-XX:+AlwaysPreTouch",
+    "-XX:G1NewSizePercent=30",
+    "-XX:G1MaxNewSizePercent=40",
+    "-XX:G1HeapRegionSize=8M",
+    "-XX:G1ReservePercent=20",
+    "-XX:G1HeapWastePercent=5",
+    "-XX:G1MixedGCCountTarget=4",
+    "-XX:InitiatingHeapOccupancyPercent=15",
+    "-XX:G1MixedGCLiveThresholdPercent=90",
+    "-XX:G1RSetUpdatingPauseTimePercent=5",
+    "-XX:SurvivorRatio=32",
+    "-XX:+PerfDisableSharedMem",
+    "-XX:MaxTenuringThreshold=1",
+    "-Dusing.aikars.flags=https://mcflags.emc.gs",
+    "-Daikars.new.flags=true",
+];
+
+// jvm arguments placed before `-jar`, the heap defaults to the ceiling when not configured
+pub(super) fn jvm_arguments(options: &JvmOptions) -> Result<Vec<String>> {
+    options.validate()?;
+
+    let ceiling = memory_ceiling_mb()?;
+    let max_memory_mb = options.max_memory_mb.or(ceiling);
+    if let Some(ceiling) = ceiling {
+        if options.min_memory_mb.is_some_and(|v| v > ceiling)
+            || max_memory_mb.is_some_and(|v| v > ceiling)
+        {
+            bail!("jvm memory exceeds the limit of {ceiling}MB");
+        }
+    }
+
+    let mut args = Vec::new();
+    if let Some(min_memory_mb) = options.min_memory_mb {
+        args.push(format!("-Xms{min_memory_mb}M"));
+    }
+    if let Some(max_memory_mb) = max_memory_mb {
+        args.push(format!("-Xmx{max_memory_mb}M"));
+    }
+    if options.aikar_flags {
+        args.extend(AIKAR_FLAGS.iter().map(|flag| flag.to_string()));
+    }
+    args.extend(options.extra_args.iter().cloned());
+
+    Ok(args)
+}
+
+// the heaps of concurrently running servers share the total limit
+pub(super) fn check_total_memory<'a>(
+    server_config: &ServerConfig,
+    running: impl Iterator<Item = &'a ServerConfig>,
+) -> Result<()> {
+    let Some(total) = memory_limit_mb(TOTAL_MEMORY_ENV)? else {
+        return Ok(());
+    };
+
+    let mut used = 0;
+    for config in running {
+        used += max_heap_mb(&config.jvm_options)?;
+    }
+    let requested = max_heap_mb(&server_config.jvm_options)?;
+    if used + requested > total {
+        bail!("not enough memory, {requested}MB requested, {used}MB of {total}MB in use");
+    }
+
+    Ok(())
+}
+
+// the total limit is set here, so the ceiling always has a value
+fn max_heap_mb(options: &JvmOptions) -> Result<u32> {
+    let ceiling = memory_ceiling_mb()?.ok_or(anyhow!("{TOTAL_MEMORY_ENV} is not set"))?;
+    Ok(options.max_memory_mb.unwrap_or(ceiling))
+}
+
+// a single server may use the whole total when no per server limit is set
+fn memory_ceiling_mb() -> Result<Option<u32>> {
+    Ok(memory_limit_mb(MAX_MEMORY_ENV)?.or(memory_limit_mb(TOTAL_MEMORY_ENV)?))
+}
+
+fn memory_limit_mb(name: &str) -> Result<Option<u32>> {
+    match std::env::var(name) {
+        Ok(value) => Ok(Some(
+            value
+                .parse()
+                .map_err(|_| anyhow!("invalid {name}: {value}"))?,
+        )),
+        Err(_) => Ok(None),
+    }
+}
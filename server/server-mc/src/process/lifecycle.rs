@@ -1,38 +1,51 @@
-use std::{process::Stdio, time::Duration};
+use std::{
+    process::{ExitStatus, Stdio},
+    time::Duration,
+};
 
 use anyhow::{anyhow, Result};
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
-    process::{Child, ChildStdin, ChildStdout, Command},
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command},
 };
-use tracing::info;
-
-use super::RUN_DIR;
+use tracing::{info, warn};
 
 pub(super) struct ProcessLifeCycle {
     child: Child,
     stdin: ChildStdin,
     stdout: Lines<BufReader<ChildStdout>>,
+    stderr: Lines<BufReader<ChildStderr>>,
+    stderr_closed: bool,
 }
 
 impl ProcessLifeCycle {
-    pub fn start(jar_path: &str) -> Result<Self> {
-        let mut child = Command::new("java")
+    pub fn start(
+        java_path: &str,
+        jvm_args: &[String],
+        jar_path: &str,
+        run_dir: &str,
+    ) -> Result<Self> {
+        let mut child = Command::new(java_path)
+            .args(jvm_args)
             .arg("-jar")
             .arg(jar_path)
             .arg("nogui")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null())
+            .stderr(Stdio::piped())
             .env_clear()
             .envs(std::env::vars().filter(|(k, _)| !k.starts_with("RUST")))
-            .current_dir(RUN_DIR)
+            .current_dir(run_dir)
             .kill_on_drop(true)
             .spawn()?;
         let stdout = child
             .stdout
             .take()
             .ok_or(anyhow!("stdout is not available"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or(anyhow!("stderr is not available"))?;
         let stdin = child
             .stdin
             .take()
@@ -42,15 +55,33 @@ impl ProcessLifeCycle {
             child,
             stdin,
             stdout: BufReader::new(stdout).lines(),
+            stderr: BufReader::new(stderr).lines(),
+            stderr_closed: false,
         })
     }
 
+    // stdout and stderr interleaved, None once stdout is closed
     pub async fn read_line(&mut self) -> Result<Option<String>> {
-        let line = self.stdout.next_line().await?;
-        if let Some(line) = &line {
-            info!("mc: {line}");
+        loop {
+            tokio::select! {
+                line = self.stdout.next_line() => {
+                    let line = line?;
+                    if let Some(line) = &line {
+                        info!("mc: {line}");
+                    }
+                    return Ok(line);
+                }
+                line = self.stderr.next_line(), if !self.stderr_closed => {
+                    match line? {
+                        Some(line) => {
+                            warn!("mc stderr: {line}");
+                            return Ok(Some(line));
+                        }
+                        None => self.stderr_closed = true,
+                    }
+                }
+            }
         }
-        Ok(line)
     }
 
     pub async fn write_command(&mut self, line: &str) -> Result<()> {
@@ -59,20 +90,33 @@ impl ProcessLifeCycle {
         Ok(())
     }
 
-    pub async fn stop_service(mut self) -> Result<()> {
+    // wait for a process which has already closed its stdout
+    pub async fn wait(mut self) -> Result<ExitStatus> {
+        Ok(self.child.wait().await?)
+    }
+
+    // also reaps a process which has already exited
+    pub async fn kill(mut self) -> Result<ExitStatus> {
+        self.child.kill().await?;
+        Ok(self.child.wait().await?)
+    }
+
+    pub async fn stop_service(mut self) -> Result<ExitStatus> {
         self.write_command("stop").await?;
 
         let wait_exit = self.child.wait();
         let max_to_wait = tokio::time::sleep(Duration::from_secs(10));
-        let exited = tokio::select! {
-            _ = wait_exit => true,
-            _ = max_to_wait => false,
+        let exit_status = tokio::select! {
+            exit_status = wait_exit => Some(exit_status?),
+            _ = max_to_wait => None,
         };
 
-        if !exited {
-            self.child.kill().await?;
+        match exit_status {
+            Some(exit_status) => Ok(exit_status),
+            None => {
+                self.child.kill().await?;
+                Ok(self.child.wait().await?)
+            }
         }
-
-        Ok(())
     }
 }
@@ -0,0 +1,201 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use anyhow::{bail, Error};
+use chrono::NaiveTime;
+use regex::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TRACE" => Ok(Self::Trace),
+            "DEBUG" => Ok(Self::Debug),
+            "INFO" => Ok(Self::Info),
+            "WARN" => Ok(Self::Warn),
+            "ERROR" => Ok(Self::Error),
+            "FATAL" => Ok(Self::Fatal),
+            _ => bail!("unknown log level: {s}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry<'a> {
+    pub time: NaiveTime,
+    pub level: LogLevel,
+    // paper does not print the thread
+    pub thread: Option<&'a str>,
+    // the fabric logger name, or the plugin prefix of a paper message
+    pub source: Option<&'a str>,
+    pub message: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogLine<'a> {
+    Entry(LogEntry<'a>),
+    // stack traces and multi-line messages belong to the last entry
+    Continuation(&'a str),
+}
+
+impl LogEntry<'_> {
+    // the server prints this once it accepts players
+    pub fn is_ready(&self) -> bool {
+        const DONE_PATTERN: &str = r#"^Done \((?:\d+\.\d+)s\)! For help, type \"help\""#;
+        static DONE_REGEX: OnceLock<Regex> = OnceLock::new();
+
+        let done_regex = DONE_REGEX
+            .get_or_init(|| Regex::new(DONE_PATTERN).expect("done regex is not available"));
+        self.level == LogLevel::Info && done_regex.is_match(self.message)
+    }
+}
+
+impl<'a> LogLine<'a> {
+    pub fn entry(&self) -> Option<&LogEntry<'a>> {
+        match self {
+            Self::Entry(entry) => Some(entry),
+            Self::Continuation(_) => None,
+        }
+    }
+}
+
+// vanilla: `[12:00:00] [Server thread/INFO]: message`
+// fabric:  `[12:00:00] [Server thread/INFO] (Minecraft) message`
+// paper:   `[12:00:00 INFO]: [Plugin] message`
+pub fn parse_log_line(line: &str) -> LogLine<'_> {
+    const LOG_LINE_PATTERN: &str = r#"^\[(?<time>\d{2}:\d{2}:\d{2})(?: (?<paper_level>[A-Z]+)\]|\] \[(?<thread>[^\]]+)/(?<level>[A-Z]+)\])(?:: | \((?<source>[^)]+)\) )(?<message>.*)$"#;
+    const PLUGIN_PREFIX_PATTERN: &str = r#"^\[(?<source>[\w .-]+)\] "#;
+    static LOG_LINE_REGEX: OnceLock<Regex> = OnceLock::new();
+    static PLUGIN_PREFIX_REGEX: OnceLock<Regex> = OnceLock::new();
+
+    let regex = LOG_LINE_REGEX
+        .get_or_init(|| Regex::new(LOG_LINE_PATTERN).expect("log line regex is not available"));
+    let Some(captures) = regex.captures(line) else {
+        return LogLine::Continuation(line);
+    };
+
+    let level = captures
+        .name("level")
+        .or(captures.name("paper_level"))
+        .and_then(|level| level.as_str().parse().ok());
+    let time = captures
+        .name("time")
+        .and_then(|time| NaiveTime::parse_from_str(time.as_str(), "%H:%M:%S").ok());
+    let (Some(level), Some(time), Some(message)) = (level, time, captures.name("message")) else {
+        return LogLine::Continuation(line);
+    };
+    let thread = captures.name("thread").map(|thread| thread.as_str());
+    let mut source = captures.name("source").map(|source| source.as_str());
+    let mut message = message.as_str();
+
+    if thread.is_none() {
+        let plugin_regex = PLUGIN_PREFIX_REGEX.get_or_init(|| {
+            Regex::new(PLUGIN_PREFIX_PATTERN).expect("plugin prefix regex is not available")
+        });
+        if let Some(prefix) = plugin_regex.captures(message) {
+            source = prefix.name("source").map(|source| source.as_str());
+            message = &message[prefix.get(0).map_or(0, |prefix| prefix.end())..];
+        }
+    }
+
+    LogLine::Entry(LogEntry {
+        time,
+        level,
+        thread,
+        source,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_entry(line: &str) -> LogEntry<'_> {
+        match parse_log_line(line) {
+            LogLine::Entry(entry) => entry,
+            LogLine::Continuation(_) => panic!("not an entry: {line}"),
+        }
+    }
+
+    #[test]
+    fn vanilla_line() {
+        let entry = parse_entry(
+            "[12:34:56] [Server thread/INFO]: Starting minecraft server version 1.21.4",
+        );
+
+        assert_eq!(entry.time, NaiveTime::from_hms_opt(12, 34, 56).unwrap());
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.thread, Some("Server thread"));
+        assert_eq!(entry.source, None);
+        assert_eq!(entry.message, "Starting minecraft server version 1.21.4");
+    }
+
+    #[test]
+    fn fabric_line() {
+        let entry =
+            parse_entry("[09:00:01] [Worker-Main-2/WARN] (Minecraft) Missing data pack fabric");
+
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.thread, Some("Worker-Main-2"));
+        assert_eq!(entry.source, Some("Minecraft"));
+        assert_eq!(entry.message, "Missing data pack fabric");
+    }
+
+    #[test]
+    fn paper_line() {
+        let entry = parse_entry("[23:59:59 ERROR]: [Essentials] Could not load config");
+
+        assert_eq!(entry.time, NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.thread, None);
+        assert_eq!(entry.source, Some("Essentials"));
+        assert_eq!(entry.message, "Could not load config");
+
+        let entry = parse_entry("[23:59:59 INFO]: Preparing level \"world\"");
+        assert_eq!(entry.source, None);
+        assert_eq!(entry.message, "Preparing level \"world\"");
+    }
+
+    #[test]
+    fn continuation_lines() {
+        for line in [
+            "java.lang.IllegalStateException: Failed to bind to port",
+            "\tat net.minecraft.server.MinecraftServer.run(MinecraftServer.java:100)",
+            "Caused by: java.net.BindException: Address already in use",
+            "",
+            "[12:00:00] [Server thread/NOTICE]: unknown level",
+            "[12:00] [Server thread/INFO]: broken time",
+        ] {
+            assert_eq!(parse_log_line(line), LogLine::Continuation(line));
+        }
+    }
+
+    #[test]
+    fn ready_line() {
+        assert!(parse_entry(
+            "[12:00:10] [Server thread/INFO]: Done (12.345s)! For help, type \"help\""
+        )
+        .is_ready());
+        assert!(parse_entry("[12:00:10 INFO]: Done (3.210s)! For help, type \"help\"").is_ready());
+        // a player can not fake it through chat, the message would not start with `Done`
+        assert!(!parse_entry(
+            "[12:00:10] [Server thread/INFO]: <Steve> Done (1.000s)! For help, type \"help\""
+        )
+        .is_ready());
+        assert!(!parse_entry(
+            "[12:00:10] [Server thread/WARN]: Done (1.000s)! For help, type \"help\""
+        )
+        .is_ready());
+    }
+}
@@ -1,19 +1,15 @@
-use std::{
-    collections::HashMap,
-    fmt::{Debug, Display},
-    path::Path,
-    sync::{Arc, OnceLock},
-};
+use std::{collections::HashMap, io::ErrorKind, sync::Arc, time::Duration};
 
-use anyhow::{bail, Result};
-use chrono::Utc;
-use regex::Regex;
+use anyhow::{anyhow, bail, Result};
+use futures_util::future::join_all;
 use tokio::{
     fs,
     sync::{
-        mpsc::{self, Receiver, Sender},
-        RwLock,
+        broadcast,
+        mpsc::{self, error::TrySendError, Receiver, Sender},
+        oneshot, RwLock,
     },
+    time::timeout,
 };
 use tracing::{info, warn};
 
@@ -21,10 +17,12 @@ use crate::dao::server_config::ServerConfig;
 
 use super::{
     callback::ProcessService,
-    communicate::Message,
-    lifecycle::ProcessLifeCycle,
-    status::{ProcessStatus, StartingStatus, StatusInfo},
-    RUN_DIR, SERVER_JAR_DIR,
+    communicate::{InstanceMessage, Message},
+    console::ConsoleLine,
+    instance::{instance_loop, server_dir, InstanceInfo, ServerInstance},
+    jvm::check_total_memory,
+    player::OnlinePlayer,
+    SERVER_PORT_BASE, SERVER_PORT_COUNT,
 };
 
 #[derive(Clone)]
@@ -34,88 +32,241 @@ pub struct Manager {
 
 struct ManagerInner {
     message_sender: Sender<Message>,
-    current_server_config: RwLock<Option<ServerConfig>>,
-    status: RwLock<HashMap<ProcessStatus, StatusInfo>>,
+    ready_timeout: Duration,
+    instances: RwLock<HashMap<u64, Arc<ServerInstance>>>,
 }
 
 impl Manager {
-    pub fn new(service: impl ProcessService) -> Manager {
-        let inner = ManagerInner::new(service);
+    pub fn new(service: impl ProcessService, ready_timeout: Duration) -> Manager {
+        let inner = ManagerInner::new(service, ready_timeout);
 
         Manager { inner }
     }
 
     pub async fn start_server_config(&self, server_config: ServerConfig) -> Result<()> {
+        let (sender, receiver) = oneshot::channel();
         self.inner
             .message_sender
-            .send(Message::StartServerConfig(server_config))
+            .send(Message::StartServerConfig {
+                server_config: Box::new(server_config),
+                result: sender,
+            })
             .await?;
 
-        Ok(())
+        receiver.await?
     }
 
-    pub async fn stop_server_config(&self) -> Result<()> {
+    pub async fn stop_server_config(&self, id: u64) -> Result<()> {
         self.inner
             .message_sender
-            .send(Message::StopServerConfig)
+            .send(Message::StopServerConfig(id))
             .await?;
 
         Ok(())
     }
 
-    pub async fn running_config(&self) -> Option<ServerConfig> {
-        (*self.inner.current_server_config.read().await).clone()
+    // servers still running after the grace period are killed when the process exits
+    pub async fn shutdown(&self, grace_period: Duration) {
+        let instances = self
+            .inner
+            .instances
+            .read()
+            .await
+            .values()
+            .filter(|instance| instance.is_alive())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for instance in &instances {
+            if let Err(e) = self.stop_server_config(instance.server_config.id).await {
+                warn!(
+                    "failed to stop server config {}: {e:?}",
+                    instance.server_config.id
+                );
+            }
+        }
+
+        // the instance loop drops its receiver once the server is stopped and backed up
+        let stopped = join_all(
+            instances
+                .iter()
+                .map(|instance| instance.message_sender.closed()),
+        );
+        if timeout(grace_period, stopped).await.is_err() {
+            warn!("servers did not stop within {grace_period:?}");
+        }
+
+        // console streams of servers that did not stop would keep the grpc server waiting
+        for instance in &instances {
+            instance.console.close().await;
+        }
     }
 
-    pub async fn status_map(&self) -> HashMap<ProcessStatus, StatusInfo> {
-        (*self.inner.status.read().await).clone()
+    pub async fn send_command(&self, id: u64, command: String) -> Result<Vec<String>> {
+        let (sender, receiver) = oneshot::channel();
+        self.inner
+            .message_sender
+            .send(Message::Command {
+                id,
+                command,
+                result: sender,
+            })
+            .await?;
+        let output = receiver.await??;
+
+        Ok(output.lines().map(str::to_owned).collect())
+    }
+
+    pub async fn is_running(&self, id: u64) -> bool {
+        self.inner
+            .instances
+            .read()
+            .await
+            .get(&id)
+            .is_some_and(|instance| instance.is_alive())
+    }
+
+    // instances whose loop has exited stay in the map until the next start, they are not running
+    pub async fn list_instances(&self) -> Vec<InstanceInfo> {
+        let instances = self
+            .inner
+            .instances
+            .read()
+            .await
+            .values()
+            .filter(|instance| instance.is_alive())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut infos = Vec::with_capacity(instances.len());
+        for instance in instances {
+            infos.push(instance.info().await);
+        }
+        infos.sort_by_key(|info| info.server_config.id);
+        infos
+    }
+
+    pub async fn list_online_players(&self, id: u64) -> Result<Vec<OnlinePlayer>> {
+        let instance = self
+            .inner
+            .instances
+            .read()
+            .await
+            .get(&id)
+            .filter(|instance| instance.is_alive())
+            .cloned()
+            .ok_or(anyhow!("server is not running"))?;
+
+        let players = instance.players.read().await.online_players();
+        Ok(players)
+    }
+
+    pub async fn subscribe_console(
+        &self,
+        id: u64,
+    ) -> Result<(Vec<ConsoleLine>, broadcast::Receiver<ConsoleLine>)> {
+        let instance = self
+            .inner
+            .instances
+            .read()
+            .await
+            .get(&id)
+            .filter(|instance| instance.is_alive())
+            .cloned()
+            .ok_or(anyhow!("server is not running"))?;
+
+        Ok(instance.console.subscribe().await)
     }
 
     pub async fn clean_world_cache(&self, server_config: &ServerConfig) -> Result<()> {
-        let world_dir = format!("{}/{}", RUN_DIR, server_config.id.to_string());
-        fs::remove_dir_all(world_dir).await?;
-        Ok(())
+        match fs::remove_dir_all(server_dir(server_config)).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
     }
 }
 
 impl ManagerInner {
-    fn new(service: impl ProcessService) -> Arc<ManagerInner> {
+    fn new(service: impl ProcessService, ready_timeout: Duration) -> Arc<ManagerInner> {
         let (sender, receiver) = mpsc::channel(10);
         let manager = Arc::new(ManagerInner {
             message_sender: sender,
-            current_server_config: RwLock::new(None),
-            status: RwLock::new(HashMap::new()),
+            ready_timeout,
+            instances: RwLock::new(HashMap::new()),
         });
         tokio::spawn(manager_loop(service, manager.clone(), receiver));
         manager
     }
 
-    async fn clean_status(&self) {
-        self.status.write().await.clear();
+    async fn start_instance(
+        &self,
+        service: &Arc<impl ProcessService>,
+        server_config: ServerConfig,
+    ) -> Result<()> {
+        let mut instances = self.instances.write().await;
+        if instances
+            .get(&server_config.id)
+            .is_some_and(|instance| instance.is_alive())
+        {
+            bail!("server config is already running");
+        }
+        instances.retain(|_, instance| instance.is_alive());
+
+        let port = (SERVER_PORT_BASE..SERVER_PORT_BASE + SERVER_PORT_COUNT)
+            .find(|port| {
+                !instances
+                    .values()
+                    .any(|instance| instance.is_alive() && instance.port == *port)
+            })
+            .ok_or(anyhow!("no free port for a new server"))?;
+        check_total_memory(
+            &server_config,
+            instances
+                .values()
+                .filter(|instance| instance.is_alive())
+                .map(|instance| &instance.server_config),
+        )?;
+
+        info!("starting server config {} on port {port}", server_config.id);
+        let (sender, receiver) = mpsc::channel(10);
+        let instance = Arc::new(ServerInstance::new(
+            server_config,
+            port,
+            sender,
+            self.ready_timeout,
+        ));
+        instances.insert(instance.server_config.id, instance.clone());
+        tokio::spawn(instance_loop(service.clone(), instance, receiver));
+
+        Ok(())
     }
 
-    async fn start_status(&self, status: ProcessStatus) {
-        info!("enter status: {:?}", status);
-        let mut map = self.status.write().await;
-        map.iter_mut()
-            .for_each(|(_, value)| value.end_time = value.end_time.or_else(|| Some(Utc::now())));
-        map.insert(
-            status,
-            StatusInfo {
-                start_time: Utc::now(),
-                ..Default::default()
-            },
-        );
+    // a starting instance does not read its messages until it is ready
+    async fn cancel_start(&self, id: u64) {
+        if let Some(instance) = self.instances.read().await.get(&id) {
+            instance.cancel_start();
+        }
     }
 
-    async fn status_error(&self, error: impl Debug + Display) {
-        warn!("status error: {:?}", error);
-        self.status.write().await.iter_mut().for_each(|(_, value)| {
-            if value.end_time.is_none() {
-                value.end_time = Some(Utc::now());
-                value.error = Some(error.to_string());
-            }
-        });
+    // never waits for the instance, the manager loop serves every server
+    async fn send_to_instance(
+        &self,
+        id: u64,
+        message: InstanceMessage,
+    ) -> Result<(), TrySendError<InstanceMessage>> {
+        let Some(instance) = self.instances.read().await.get(&id).cloned() else {
+            return Err(TrySendError::Closed(message));
+        };
+
+        instance.message_sender.try_send(message)
+    }
+
+    async fn instance_sender(&self, id: u64) -> Option<Sender<InstanceMessage>> {
+        self.instances
+            .read()
+            .await
+            .get(&id)
+            .map(|instance| instance.message_sender.clone())
     }
 }
 
@@ -124,133 +275,47 @@ async fn manager_loop(
     manager: Arc<ManagerInner>,
     mut receiver: Receiver<Message>,
 ) {
-    loop {
-        let Some(message) = receiver.recv().await else {
-            break;
-        };
-
-        let Message::StartServerConfig(server_config) = message else {
-            continue;
-        };
-
-        manager.clean_status().await;
-        *(manager.current_server_config.write().await) = Some(server_config.clone());
-
-        let mut process_lifecycle = match starting_service(&service, &manager, &server_config).await
-        {
-            Ok(process_lifecycle) => process_lifecycle,
-            Err(e) => {
-                manager.status_error(e).await;
-                continue;
+    let service = Arc::new(service);
+
+    while let Some(message) = receiver.recv().await {
+        match message {
+            Message::StartServerConfig {
+                server_config,
+                result,
+            } => {
+                let _ = result.send(manager.start_instance(&service, *server_config).await);
             }
-        };
-
-        service.server_started().await;
-        manager.start_status(ProcessStatus::Running).await;
-
-        loop {
-            tokio::select! {
-                message = receiver.recv() => {
-                    let Some(message) = message else {
-                        break;
-                    };
-
-                    match message {
-                        Message::StartServerConfig(_server_config) => continue,
-                        Message::StopServerConfig => break,
-                    }
-                },
-                line = process_lifecycle.read_line() => {
-                    match line {
-                        Ok(Some(line)) => {
-                            service.stdout_line(&line).await;
-                        },
-                        Ok(None) | Err(_) => {
-                            break;
+            Message::StopServerConfig(id) => {
+                manager.cancel_start(id).await;
+                match manager.send_to_instance(id, InstanceMessage::Stop).await {
+                    Ok(()) => {}
+                    // the queue is full of commands, waiting for room must not block other servers
+                    Err(TrySendError::Full(message)) => {
+                        if let Some(sender) = manager.instance_sender(id).await {
+                            tokio::spawn(async move {
+                                let _ = sender.send(message).await;
+                            });
                         }
+                    }
+                    Err(TrySendError::Closed(_)) => warn!("server config {id} is not running"),
+                }
+            }
+            Message::Command {
+                id,
+                command,
+                result,
+            } => {
+                let message = InstanceMessage::Command { command, result };
+                if let Err(e) = manager.send_to_instance(id, message).await {
+                    let error = match e {
+                        TrySendError::Full(_) => anyhow!("server is busy or still starting"),
+                        TrySendError::Closed(_) => anyhow!("server is not running"),
                     };
+                    if let InstanceMessage::Command { result, .. } = e.into_inner() {
+                        let _ = result.send(Err(error));
+                    }
                 }
-            };
-        }
-
-        info!("stoping service");
-        manager.start_status(ProcessStatus::Terminating).await;
-        if let Err(e) = process_lifecycle.stop_service().await {
-            manager.status_error(e).await;
-        }
-
-        service.server_stop().await;
-
-        manager.start_status(ProcessStatus::Terminated).await;
-    }
-}
-
-async fn starting_service(
-    service: &impl ProcessService,
-    manager: &ManagerInner,
-    server_config: &ServerConfig,
-) -> Result<ProcessLifeCycle> {
-    let jar_path = format!("{}/{}.jar", SERVER_JAR_DIR, &server_config.mc_version);
-    let world_path = format!("{}/{}", RUN_DIR, server_config.id);
-
-    if let Some(parent) = Path::new(&jar_path).parent() {
-        if !parent.exists() {
-            std::fs::create_dir_all(parent)?;
-        }
-    }
-    if !Path::new(RUN_DIR).exists() {
-        std::fs::create_dir_all(Path::new(RUN_DIR))?;
-    }
-    info!("starting service, jar_path: {jar_path}, world_path: {world_path}");
-
-    // download jar
-    manager
-        .start_status(ProcessStatus::Starting(StartingStatus::DownloadServerJar))
-        .await;
-    if !Path::new(&jar_path).exists() {
-        service
-            .download_server_jar(&server_config.mc_version, &jar_path)
-            .await?;
-    }
-
-    // download world
-    manager
-        .start_status(ProcessStatus::Starting(StartingStatus::DownloadWorld))
-        .await;
-    if !Path::new(&world_path).exists() {
-        if let Some(world_uri) = &server_config.world_uri {
-            service.download_world(&world_uri, &world_path).await?;
-        }
-    }
-
-    // initialize configuration
-    manager
-        .start_status(ProcessStatus::Starting(
-            StartingStatus::InitializeConfigFile,
-        ))
-        .await;
-    service
-        .initialize_config_files(RUN_DIR, &server_config.id.to_string(), server_config)
-        .await?;
-
-    // starting server and wait to ready
-    manager
-        .start_status(ProcessStatus::Starting(
-            StartingStatus::WaitingForServerReady,
-        ))
-        .await;
-    let mut lifecycle = ProcessLifeCycle::start(&jar_path)?;
-    // loop to detect 'Done' message
-    while let Some(message) = lifecycle.read_line().await? {
-        const DONE_PATTERN: &str = r#"\[(?:\d{2}:?){3}\] \[Server thread/INFO\]: Done \((?:\d+\.\d+)s\)! For help, type \"help\""#;
-        static DONE_REGEX: OnceLock<Regex> = OnceLock::new();
-
-        let done_regex = DONE_REGEX
-            .get_or_init(|| Regex::new(DONE_PATTERN).expect("done regex is not available"));
-        if done_regex.is_match(&message) {
-            return Ok(lifecycle);
+            }
         }
     }
-
-    bail!("process shutdown before getting ready")
 }
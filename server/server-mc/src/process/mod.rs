@@ -1,8 +1,21 @@
+pub mod addon;
+pub mod backup;
 pub mod callback;
 pub mod communicate;
+pub mod console;
+pub mod instance;
+pub mod java;
+pub mod jvm;
 pub mod lifecycle;
+pub mod log;
 pub mod manager;
+pub mod ping;
+pub mod player;
+pub mod rcon;
 pub mod status;
 
 pub(crate) const SERVER_JAR_DIR: &str = "/var/lib/mc_server/bin";
 pub(crate) const RUN_DIR: &str = "/var/lib/mc_server/run";
+
+pub(crate) const SERVER_PORT_BASE: u16 = 25565;
+pub(crate) const SERVER_PORT_COUNT: u16 = 10;
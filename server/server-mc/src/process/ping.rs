@@ -0,0 +1,324 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::{timeout, Instant},
+};
+use tracing::warn;
+
+use super::instance::ServerInstance;
+
+pub(super) const PING_INTERVAL: Duration = Duration::from_secs(30);
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+const PING_HOST: &str = "127.0.0.1";
+
+// -1 asks the server to report its own protocol version
+const HANDSHAKE_PROTOCOL_VERSION: i32 = -1;
+const NEXT_STATE_STATUS: i32 = 1;
+const PACKET_HANDSHAKE: i32 = 0x00;
+const PACKET_STATUS: i32 = 0x00;
+const PACKET_PING: i32 = 0x01;
+// status responses are small, anything larger is a broken stream
+const MAX_PACKET_LEN: usize = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+    pub version: String,
+    pub protocol: i32,
+    pub online_players: u32,
+    pub max_players: u32,
+    pub players: Vec<PlayerSample>,
+    pub latency: Duration,
+    pub update_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlayerSample {
+    pub name: String,
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusResponse {
+    version: StatusVersion,
+    players: Option<StatusPlayers>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusVersion {
+    name: String,
+    protocol: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusPlayers {
+    max: u32,
+    online: u32,
+    #[serde(default)]
+    sample: Vec<PlayerSample>,
+}
+
+// periodic probe of a running server, the last result is kept on the instance
+pub(super) async fn ping_running_instance(instance: Arc<ServerInstance>) {
+    let status = match timeout(PING_TIMEOUT, ping(PING_HOST, instance.port)).await {
+        Ok(Ok(status)) => Some(status),
+        Ok(Err(e)) => {
+            warn!(
+                "[{}] server list ping failed: {e:?}",
+                instance.server_config.id
+            );
+            None
+        }
+        Err(_) => {
+            warn!("[{}] server list ping timed out", instance.server_config.id);
+            None
+        }
+    };
+    instance.set_server_status(status).await;
+}
+
+// https://minecraft.wiki/w/Java_Edition_protocol/Server_List_Ping
+pub(super) async fn ping(host: &str, port: u16) -> Result<ServerStatus> {
+    let mut stream = TcpStream::connect((host, port)).await?;
+    stream.set_nodelay(true)?;
+
+    let mut handshake = Vec::new();
+    write_var_int(&mut handshake, HANDSHAKE_PROTOCOL_VERSION);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_var_int(&mut handshake, NEXT_STATE_STATUS);
+    write_packet(&mut stream, PACKET_HANDSHAKE, &handshake).await?;
+    write_packet(&mut stream, PACKET_STATUS, &[]).await?;
+
+    let (id, payload) = read_packet(&mut stream).await?;
+    if id != PACKET_STATUS {
+        bail!("unexpected status packet id: {id}");
+    }
+    let mut payload = payload.as_slice();
+    let json = read_string(&mut payload).await?;
+    let response: StatusResponse = serde_json::from_str(&json)?;
+
+    let payload = Utc::now().timestamp_millis();
+    let ping_time = Instant::now();
+    write_packet(&mut stream, PACKET_PING, &payload.to_be_bytes()).await?;
+    let (id, pong) = read_packet(&mut stream).await?;
+    let latency = ping_time.elapsed();
+    if id != PACKET_PING || pong != payload.to_be_bytes() {
+        bail!("unexpected pong packet");
+    }
+
+    let players = response.players.unwrap_or(StatusPlayers {
+        max: 0,
+        online: 0,
+        sample: Vec::new(),
+    });
+    Ok(ServerStatus {
+        version: response.version.name,
+        protocol: response.version.protocol,
+        online_players: players.online,
+        max_players: players.max,
+        players: players.sample,
+        latency,
+        update_time: Utc::now(),
+    })
+}
+
+async fn write_packet(stream: &mut TcpStream, id: i32, data: &[u8]) -> Result<()> {
+    let mut body = Vec::with_capacity(data.len() + 5);
+    write_var_int(&mut body, id);
+    body.extend_from_slice(data);
+
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    write_var_int(&mut packet, body.len() as i32);
+    packet.extend_from_slice(&body);
+    stream.write_all(&packet).await?;
+
+    Ok(())
+}
+
+async fn read_packet(stream: &mut TcpStream) -> Result<(i32, Vec<u8>)> {
+    let len = read_var_int(stream).await?;
+    if len <= 0 || len as usize > MAX_PACKET_LEN {
+        bail!("invalid packet length: {len}");
+    }
+
+    let mut body = vec![0; len as usize];
+    stream.read_exact(&mut body).await?;
+    let mut body = body.as_slice();
+    let id = read_var_int(&mut body).await?;
+
+    Ok((id, body.to_vec()))
+}
+
+fn write_var_int(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7f | 0x80) as u8);
+        value >>= 7;
+    }
+}
+
+async fn read_var_int(reader: &mut (impl AsyncRead + Unpin)) -> Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let byte = reader.read_u8().await?;
+        value |= ((byte & 0x7f) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+
+    bail!("var int is too big")
+}
+
+fn write_string(buf: &mut Vec<u8>, value: &str) {
+    write_var_int(buf, value.len() as i32);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+async fn read_string(reader: &mut (impl AsyncRead + Unpin)) -> Result<String> {
+    let len = read_var_int(reader).await?;
+    if len < 0 || len as usize > MAX_PACKET_LEN {
+        bail!("invalid string length: {len}");
+    }
+
+    let mut buf = vec![0; len as usize];
+    reader.read_exact(&mut buf).await?;
+
+    Ok(String::from_utf8(buf)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const STATUS_JSON: &str = r#"{"version":{"name":"1.21.4","protocol":769},"players":{"max":20,"online":1,"sample":[{"name":"Notch","id":"069a79f4-44e9-4726-a5be-fca90e38aaf5"}]},"description":{"text":"A Minecraft Server"}}"#;
+
+    fn status_packet(json: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+        write_var_int(&mut body, PACKET_STATUS);
+        write_string(&mut body, json);
+
+        let mut packet = Vec::new();
+        write_var_int(&mut packet, body.len() as i32);
+        packet.extend_from_slice(&body);
+        packet
+    }
+
+    // checks the handshake and status request and sends `response`,
+    // then echoes the ping when `pong` is set or closes the connection
+    async fn fake_server(response: Vec<u8>, pong: bool) -> (u16, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind((PING_HOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (id, handshake) = read_packet(&mut stream).await.unwrap();
+            assert_eq!(id, PACKET_HANDSHAKE);
+            let mut handshake = handshake.as_slice();
+            assert_eq!(
+                read_var_int(&mut handshake).await.unwrap(),
+                HANDSHAKE_PROTOCOL_VERSION
+            );
+            assert_eq!(read_string(&mut handshake).await.unwrap(), PING_HOST);
+            assert_eq!(handshake.read_u16().await.unwrap(), port);
+            assert_eq!(
+                read_var_int(&mut handshake).await.unwrap(),
+                NEXT_STATE_STATUS
+            );
+            assert_eq!(
+                read_packet(&mut stream).await.unwrap(),
+                (PACKET_STATUS, vec![])
+            );
+
+            stream.write_all(&response).await.unwrap();
+            if pong {
+                let (id, payload) = read_packet(&mut stream).await.unwrap();
+                assert_eq!(id, PACKET_PING);
+                write_packet(&mut stream, PACKET_PING, &payload)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn var_int_boundaries() {
+        let cases: [(i32, &[u8]); 10] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (255, &[0xff, 0x01]),
+            (25565, &[0xdd, 0xc7, 0x01]),
+            (2097151, &[0xff, 0xff, 0x7f]),
+            (i32::MAX, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+            (i32::MIN, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+        ];
+
+        for (value, bytes) in cases {
+            let mut buf = Vec::new();
+            write_var_int(&mut buf, value);
+            assert_eq!(buf, bytes, "encoding {value}");
+
+            let mut reader = bytes;
+            assert_eq!(read_var_int(&mut reader).await.unwrap(), value);
+        }
+    }
+
+    #[tokio::test]
+    async fn var_int_too_big() {
+        let mut reader: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+
+        assert!(read_var_int(&mut reader).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn status_response() {
+        let (port, server) = fake_server(status_packet(STATUS_JSON), true).await;
+
+        let status = ping(PING_HOST, port).await.unwrap();
+        server.await.unwrap();
+        assert_eq!(status.version, "1.21.4");
+        assert_eq!(status.protocol, 769);
+        assert_eq!(status.online_players, 1);
+        assert_eq!(status.max_players, 20);
+        assert_eq!(status.players.len(), 1);
+        assert_eq!(status.players[0].name, "Notch");
+    }
+
+    #[tokio::test]
+    async fn status_without_players() {
+        let json = r#"{"version":{"name":"1.21.4","protocol":769},"description":"motd"}"#;
+        let (port, server) = fake_server(status_packet(json), true).await;
+
+        let status = ping(PING_HOST, port).await.unwrap();
+        server.await.unwrap();
+        assert_eq!(status.online_players, 0);
+        assert_eq!(status.max_players, 0);
+        assert!(status.players.is_empty());
+    }
+
+    #[tokio::test]
+    async fn truncated_response() {
+        let mut response = status_packet(STATUS_JSON);
+        response.truncate(response.len() / 2);
+        let (port, server) = fake_server(response, false).await;
+
+        assert!(ping(PING_HOST, port).await.is_err());
+        server.await.unwrap();
+    }
+}
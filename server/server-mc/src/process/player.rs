@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use tracing::{info, warn};
+
+use super::{callback::ProcessService, instance::ServerInstance, log::LogEntry};
+
+#[derive(Debug, Clone)]
+pub struct OnlinePlayer {
+    pub name: String,
+    pub uuid: String,
+    pub join_time: DateTime<Utc>,
+    session_id: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum PlayerEvent<'a> {
+    Uuid { name: &'a str, uuid: &'a str },
+    Joined { name: &'a str },
+    Left { name: &'a str },
+}
+
+// online players of one server, uuids are logged by the authenticator before the join line
+#[derive(Debug, Default)]
+pub(super) struct PlayerTracker {
+    uuids: HashMap<String, String>,
+    online: HashMap<String, OnlinePlayer>,
+}
+
+impl PlayerTracker {
+    pub fn online_players(&self) -> Vec<OnlinePlayer> {
+        let mut players = self.online.values().cloned().collect::<Vec<_>>();
+        players.sort_by_key(|player| player.join_time);
+        players
+    }
+
+    pub fn clear(&mut self) {
+        self.uuids.clear();
+        self.online.clear();
+    }
+}
+
+// database calls run in their own tasks, a slow insert must not stall draining stdout
+pub(super) async fn handle_line(
+    service: &Arc<impl ProcessService>,
+    instance: &Arc<ServerInstance>,
+    entry: &LogEntry<'_>,
+) {
+    let Some(event) = parse_player_event(entry.message) else {
+        return;
+    };
+    let server_config = &instance.server_config;
+
+    match event {
+        PlayerEvent::Uuid { name, uuid } => {
+            let mut players = instance.players.write().await;
+            players.uuids.insert(name.to_owned(), uuid.to_owned());
+        }
+        PlayerEvent::Joined { name } => {
+            let join_time = Utc::now();
+            let uuid = {
+                let mut players = instance.players.write().await;
+                let uuid = players.uuids.remove(name).unwrap_or_default();
+                players.online.insert(
+                    name.to_owned(),
+                    OnlinePlayer {
+                        name: name.to_owned(),
+                        uuid: uuid.clone(),
+                        join_time,
+                        session_id: None,
+                    },
+                );
+                uuid
+            };
+            info!("[{}] player {name} ({uuid}) joined", server_config.id);
+            tokio::spawn(record_session(
+                service.clone(),
+                instance.clone(),
+                name.to_owned(),
+                uuid,
+                join_time,
+            ));
+        }
+        PlayerEvent::Left { name } => {
+            info!("[{}] player {name} left", server_config.id);
+            let player = instance.players.write().await.online.remove(name);
+            if let Some(session_id) = player.and_then(|player| player.session_id) {
+                tokio::spawn(finish_session(
+                    service.clone(),
+                    instance.clone(),
+                    session_id,
+                ));
+            }
+        }
+    }
+}
+
+async fn record_session(
+    service: Arc<impl ProcessService>,
+    instance: Arc<ServerInstance>,
+    name: String,
+    uuid: String,
+    join_time: DateTime<Utc>,
+) {
+    let session_id = match service
+        .player_joined(&instance.server_config, &name, &uuid)
+        .await
+    {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            warn!(
+                "[{}] failed to record session: {e:?}",
+                instance.server_config.id
+            );
+            return;
+        }
+    };
+
+    // the player may have left, or the server stopped, while the session was saved
+    if let Some(player) = instance
+        .players
+        .write()
+        .await
+        .online
+        .get_mut(&name)
+        .filter(|player| player.join_time == join_time)
+    {
+        player.session_id = Some(session_id);
+        return;
+    }
+    finish_session(service, instance, session_id).await;
+}
+
+async fn finish_session(
+    service: Arc<impl ProcessService>,
+    instance: Arc<ServerInstance>,
+    session_id: u64,
+) {
+    if let Err(e) = service.player_left(session_id).await {
+        warn!(
+            "[{}] failed to finish session: {e:?}",
+            instance.server_config.id
+        );
+    }
+}
+
+fn parse_player_event(message: &str) -> Option<PlayerEvent<'_>> {
+    const PLAYER_EVENT_PATTERN: &str = r#"^(?:UUID of player (?<uuid_name>\w{1,16}) is (?<uuid>[0-9a-fA-F-]{32,36})|(?<joined>\w{1,16}) joined the game|(?<left>\w{1,16}) left the game)$"#;
+    static PLAYER_EVENT_REGEX: OnceLock<Regex> = OnceLock::new();
+
+    let regex = PLAYER_EVENT_REGEX.get_or_init(|| {
+        Regex::new(PLAYER_EVENT_PATTERN).expect("player event regex is not available")
+    });
+    let captures = regex.captures(message)?;
+
+    if let (Some(name), Some(uuid)) = (captures.name("uuid_name"), captures.name("uuid")) {
+        return Some(PlayerEvent::Uuid {
+            name: name.as_str(),
+            uuid: uuid.as_str(),
+        });
+    }
+    if let Some(name) = captures.name("joined") {
+        return Some(PlayerEvent::Joined {
+            name: name.as_str(),
+        });
+    }
+    captures.name("left").map(|name| PlayerEvent::Left {
+        name: name.as_str(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::log::parse_log_line;
+
+    fn event(line: &str) -> Option<PlayerEvent<'_>> {
+        parse_player_event(parse_log_line(line).entry()?.message)
+    }
+
+    #[test]
+    fn vanilla_events() {
+        assert_eq!(
+            event("[12:00:00] [User Authenticator #1/INFO]: UUID of player Steve is 8667ba71-b85a-4004-af54-457a9734eed7"),
+            Some(PlayerEvent::Uuid {
+                name: "Steve",
+                uuid: "8667ba71-b85a-4004-af54-457a9734eed7"
+            })
+        );
+        assert_eq!(
+            event("[12:00:01] [Server thread/INFO]: Steve joined the game"),
+            Some(PlayerEvent::Joined { name: "Steve" })
+        );
+        assert_eq!(
+            event("[12:30:00] [Server thread/INFO]: Steve left the game"),
+            Some(PlayerEvent::Left { name: "Steve" })
+        );
+    }
+
+    #[test]
+    fn paper_and_fabric_events() {
+        assert_eq!(
+            event("[12:00:01 INFO]: Alex_2 joined the game"),
+            Some(PlayerEvent::Joined { name: "Alex_2" })
+        );
+        assert_eq!(
+            event("[12:00:01] [Server thread/INFO] (Minecraft) Alex_2 left the game"),
+            Some(PlayerEvent::Left { name: "Alex_2" })
+        );
+    }
+
+    #[test]
+    fn chat_is_not_an_event() {
+        assert_eq!(
+            event("[12:00:02] [Server thread/INFO]: <Steve> Alex joined the game"),
+            None
+        );
+        assert_eq!(event("\tat Steve joined the game"), None);
+    }
+}
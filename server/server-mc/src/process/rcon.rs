@@ -0,0 +1,306 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use rand::{distributions::Alphanumeric, Rng};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+use tracing::{info, warn};
+
+use super::SERVER_PORT_COUNT;
+
+pub(super) const RCON_HOST: &str = "127.0.0.1";
+const RCON_PASSWORD_LEN: usize = 32;
+// a `save-all flush` on a large world answers only after the save is done
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+const PACKET_RESPONSE_VALUE: i32 = 0;
+const PACKET_EXEC_COMMAND: i32 = 2;
+const PACKET_AUTH: i32 = 3;
+const AUTH_FAILED_ID: i32 = -1;
+// the server splits responses into 4096 byte bodies, commands are limited to 1446 bytes
+const MAX_COMMAND_LEN: usize = 1446;
+const MAX_PACKET_LEN: usize = 4096 + 10;
+const MAX_RESPONSE_LEN: usize = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct RconConfig {
+    pub port: u16,
+    pub password: String,
+}
+
+impl RconConfig {
+    // rcon ports follow the game port range, so they never collide with each other
+    pub(super) fn generate(server_port: u16) -> Self {
+        let password = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(RCON_PASSWORD_LEN)
+            .map(char::from)
+            .collect();
+
+        Self {
+            port: server_port + SERVER_PORT_COUNT,
+            password,
+        }
+    }
+}
+
+// connects on first use and again after the connection is broken
+pub(super) struct RconSession {
+    config: RconConfig,
+    client: Option<RconClient>,
+}
+
+impl RconSession {
+    pub fn new(config: RconConfig) -> Self {
+        Self {
+            config,
+            client: None,
+        }
+    }
+
+    pub async fn command(&mut self, command: &str) -> Result<String> {
+        let client = match &mut self.client {
+            Some(client) => client,
+            None => {
+                info!("connecting rcon on port {}", self.config.port);
+                let client =
+                    RconClient::connect(RCON_HOST, self.config.port, &self.config.password).await?;
+                self.client.insert(client)
+            }
+        };
+
+        let result = timeout(COMMAND_TIMEOUT, client.command(command))
+            .await
+            .map_err(|_| anyhow!("rcon command timed out"))
+            .and_then(|result| result);
+        if let Err(e) = &result {
+            warn!("rcon command failed, reconnect on next command: {e}");
+            self.client = None;
+        }
+
+        result
+    }
+}
+
+// https://minecraft.wiki/w/RCON
+pub(super) struct RconClient {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl RconClient {
+    pub async fn connect(host: &str, port: u16, password: &str) -> Result<Self> {
+        let stream = TcpStream::connect((host, port)).await?;
+        stream.set_nodelay(true)?;
+        let mut client = Self { stream, next_id: 1 };
+
+        let id = client.next_id();
+        client.write_packet(id, PACKET_AUTH, password).await?;
+        // some servers send an empty response value before the auth response
+        loop {
+            let (response_id, r#type, _) = client.read_packet().await?;
+            if response_id == AUTH_FAILED_ID {
+                bail!("rcon authentication failed");
+            }
+            if response_id == id && r#type == PACKET_EXEC_COMMAND {
+                return Ok(client);
+            }
+        }
+    }
+
+    // an empty packet is sent after the command, its echo marks the end of a split response
+    pub async fn command(&mut self, command: &str) -> Result<String> {
+        if command.len() > MAX_COMMAND_LEN {
+            bail!("command is too long");
+        }
+
+        let id = self.next_id();
+        let end_id = self.next_id();
+        self.write_packet(id, PACKET_EXEC_COMMAND, command).await?;
+        self.write_packet(end_id, PACKET_RESPONSE_VALUE, "").await?;
+
+        let mut response = Vec::new();
+        loop {
+            let (response_id, _, body) = self.read_packet().await?;
+            if response_id == end_id {
+                break;
+            }
+            if response_id == id {
+                if response.len() + body.len() > MAX_RESPONSE_LEN {
+                    bail!("rcon response is too large");
+                }
+                response.extend_from_slice(&body);
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+
+    fn next_id(&mut self) -> i32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+        id
+    }
+
+    async fn write_packet(&mut self, id: i32, r#type: i32, body: &str) -> Result<()> {
+        let len = 4 + 4 + body.len() + 2;
+        let mut packet = Vec::with_capacity(4 + len);
+        packet.extend_from_slice(&(len as i32).to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&r#type.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        self.stream.write_all(&packet).await?;
+
+        Ok(())
+    }
+
+    async fn read_packet(&mut self) -> Result<(i32, i32, Vec<u8>)> {
+        let len = self.stream.read_i32_le().await?;
+        if len < 10 || len as usize > MAX_PACKET_LEN {
+            bail!("invalid rcon packet length: {len}");
+        }
+
+        let id = self.stream.read_i32_le().await?;
+        let r#type = self.stream.read_i32_le().await?;
+        let mut body = vec![0; len as usize - 8];
+        self.stream.read_exact(&mut body).await?;
+        // drop the body terminator and the trailing empty string
+        body.truncate(body.len() - 2);
+
+        Ok((id, r#type, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    async fn read_packet(stream: &mut TcpStream) -> (i32, i32, String) {
+        let len = stream.read_i32_le().await.unwrap();
+        let id = stream.read_i32_le().await.unwrap();
+        let r#type = stream.read_i32_le().await.unwrap();
+        let mut body = vec![0; len as usize - 8];
+        stream.read_exact(&mut body).await.unwrap();
+        assert_eq!(&body[body.len() - 2..], &[0, 0]);
+        body.truncate(body.len() - 2);
+
+        (id, r#type, String::from_utf8(body).unwrap())
+    }
+
+    fn packet(id: i32, r#type: i32, body: &str) -> Vec<u8> {
+        let mut packet = Vec::new();
+        packet.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&r#type.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        packet
+    }
+
+    // accepts one connection and answers the auth packet, the password is `secret`
+    async fn fake_server() -> (u16, tokio::task::JoinHandle<TcpStream>) {
+        let listener = TcpListener::bind((RCON_HOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (id, r#type, body) = read_packet(&mut stream).await;
+            assert_eq!(r#type, PACKET_AUTH);
+
+            let response_id = if body == "secret" { id } else { AUTH_FAILED_ID };
+            // vanilla sends an empty response value before the auth response
+            let mut response = packet(id, PACKET_RESPONSE_VALUE, "");
+            response.extend(packet(response_id, PACKET_EXEC_COMMAND, ""));
+            stream.write_all(&response).await.unwrap();
+            stream
+        });
+
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn auth_success() {
+        let (port, server) = fake_server().await;
+
+        RconClient::connect(RCON_HOST, port, "secret")
+            .await
+            .unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn auth_failure() {
+        let (port, server) = fake_server().await;
+
+        let result = RconClient::connect(RCON_HOST, port, "wrong").await;
+        assert!(result.is_err());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn split_response_until_end_marker() {
+        let (port, server) = fake_server().await;
+        let server = tokio::spawn(async move {
+            let mut stream = server.await.unwrap();
+            let (id, r#type, body) = read_packet(&mut stream).await;
+            assert_eq!((r#type, body.as_str()), (PACKET_EXEC_COMMAND, "list"));
+            let (end_id, r#type, body) = read_packet(&mut stream).await;
+            assert_eq!((r#type, body.as_str()), (PACKET_RESPONSE_VALUE, ""));
+
+            let mut response = packet(id, PACKET_RESPONSE_VALUE, "There are 2 of ");
+            response.extend(packet(id, PACKET_RESPONSE_VALUE, "a max of 20 players"));
+            response.extend(packet(end_id, PACKET_RESPONSE_VALUE, ""));
+            // a packet header may arrive in a different read than its body
+            let (first, second) = response.split_at(7);
+            stream.write_all(first).await.unwrap();
+            stream.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            stream.write_all(second).await.unwrap();
+        });
+
+        let mut client = RconClient::connect(RCON_HOST, port, "secret")
+            .await
+            .unwrap();
+        let output = client.command("list").await.unwrap();
+        assert_eq!(output, "There are 2 of a max of 20 players");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn empty_response_ends_at_end_marker() {
+        let (port, server) = fake_server().await;
+        let server = tokio::spawn(async move {
+            let mut stream = server.await.unwrap();
+            let (id, _, _) = read_packet(&mut stream).await;
+            let (end_id, _, _) = read_packet(&mut stream).await;
+
+            let mut response = packet(id, PACKET_RESPONSE_VALUE, "");
+            response.extend(packet(end_id, PACKET_RESPONSE_VALUE, ""));
+            stream.write_all(&response).await.unwrap();
+        });
+
+        let mut client = RconClient::connect(RCON_HOST, port, "secret")
+            .await
+            .unwrap();
+        assert_eq!(client.command("save-all").await.unwrap(), "");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn command_too_long() {
+        let (port, server) = fake_server().await;
+
+        let mut client = RconClient::connect(RCON_HOST, port, "secret")
+            .await
+            .unwrap();
+        let command = "a".repeat(MAX_COMMAND_LEN + 1);
+        assert!(client.command(&command).await.is_err());
+        server.await.unwrap();
+    }
+}
@@ -1,17 +1,28 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
 use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ProcessStatus {
+    // keyed by crash count, ordered first so a running attempt is always the latest status
+    Crashed(u32),
     Starting(StartingStatus),
     Running,
     Terminating,
+    // a stop arrived before the server was ready
+    Cancelled,
     Terminated,
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum StartingStatus {
+    SelectJavaRuntime,
     DownloadServerJar,
     DownloadWorld,
+    InstallAddons,
     InitializeConfigFile,
     WaitingForServerReady,
 }
@@ -21,4 +32,94 @@ pub struct StatusInfo {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub error: Option<String>,
+    pub exit_code: Option<i32>,
+    // only reported by the download stages
+    pub progress: Option<StageProgress>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageProgress {
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+    pub entries_extracted: u64,
+    pub entries_total: Option<u64>,
+}
+
+// shared with the download callbacks, the blocking unzip task included
+// a total of 0 means unknown
+#[derive(Debug, Clone, Default)]
+pub struct ProgressCounter(Arc<ProgressCounterInner>);
+
+#[derive(Debug, Default)]
+struct ProgressCounterInner {
+    bytes_done: AtomicU64,
+    bytes_total: AtomicU64,
+    entries_extracted: AtomicU64,
+    entries_total: AtomicU64,
+}
+
+impl ProgressCounter {
+    pub fn reset(&self) {
+        self.set_bytes_total(None);
+        self.0.bytes_done.store(0, Ordering::Relaxed);
+        self.set_entries_total(None);
+        self.0.entries_extracted.store(0, Ordering::Relaxed);
+    }
+
+    pub fn set_bytes_total(&self, total: Option<u64>) {
+        self.0
+            .bytes_total
+            .store(total.unwrap_or_default(), Ordering::Relaxed);
+    }
+
+    pub fn reset_bytes(&self) {
+        self.0.bytes_done.store(0, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.0.bytes_done.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn set_entries_total(&self, total: Option<u64>) {
+        self.0
+            .entries_total
+            .store(total.unwrap_or_default(), Ordering::Relaxed);
+    }
+
+    pub fn add_entry(&self) {
+        self.0.entries_extracted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // None until the stage reports anything
+    pub fn snapshot(&self) -> Option<StageProgress> {
+        let non_zero = |value: u64| (value > 0).then_some(value);
+        let progress = StageProgress {
+            bytes_done: self.0.bytes_done.load(Ordering::Relaxed),
+            bytes_total: non_zero(self.0.bytes_total.load(Ordering::Relaxed)),
+            entries_extracted: self.0.entries_extracted.load(Ordering::Relaxed),
+            entries_total: non_zero(self.0.entries_total.load(Ordering::Relaxed)),
+        };
+
+        (progress != StageProgress::default()).then_some(progress)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunResult {
+    Stopped,
+    Crashed,
+    Failed,
+    Cancelled,
+}
+
+// one attempt of the instance loop, reported when the process is gone
+#[derive(Debug, Clone)]
+pub struct RunRecord {
+    pub start_time: DateTime<Utc>,
+    pub stop_time: DateTime<Utc>,
+    pub result: RunResult,
+    pub error: Option<String>,
+    pub exit_code: Option<i32>,
+    // ordered by start time, the last one is the stage the run ended in
+    pub status: Vec<(ProcessStatus, StatusInfo)>,
 }
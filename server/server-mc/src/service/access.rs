@@ -0,0 +1,307 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use common::tonic_idl_gen::{
+    AddServerConfigAccessRequest, AddServerConfigAccessResponse, DeleteServerConfigAccessRequest,
+    DeleteServerConfigAccessResponse, ListServerConfigAccessRequest,
+    ListServerConfigAccessResponse,
+};
+use reqwest::Client;
+use serde::Serialize;
+use server_common::db::context::{Context, ContextRef};
+use sqlx::Database;
+use tracing::{info, warn};
+
+use super::uuid::{resolve_player_uuid, MojangResolver, OfflineResolver};
+use crate::{
+    dao::{
+        access::{AccessList, AccessRepository, ServerConfigAccess},
+        server_config::ServerConfigRepository,
+    },
+    process::manager::Manager,
+};
+
+const PLAYER_NAME_MAX_LEN: usize = 16;
+const BAN_REASON_MAX_LEN: usize = 256;
+const DEFAULT_OP_LEVEL: u32 = 4;
+const MAX_OP_LEVEL: u32 = 4;
+
+#[derive(Debug, Serialize)]
+struct WhitelistEntry<'a> {
+    uuid: &'a str,
+    name: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpEntry<'a> {
+    uuid: &'a str,
+    name: &'a str,
+    level: u32,
+    bypasses_player_limit: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct BannedPlayerEntry<'a> {
+    uuid: &'a str,
+    name: &'a str,
+    created: String,
+    source: &'static str,
+    expires: &'static str,
+    reason: &'a str,
+}
+
+pub async fn add_server_config_access<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    client: &Client,
+    manager: &Manager,
+    req: AddServerConfigAccessRequest,
+) -> Result<AddServerConfigAccessResponse>
+where
+    for<'db> Context<'db, DB>: AccessRepository + ServerConfigRepository,
+{
+    validate_player_name(&req.player_name)?;
+    let list_type = req.list().into();
+
+    let server_config = db
+        .get_server_config_by_id(req.server_config_id)
+        .await?
+        .ok_or(anyhow!("server config not found"))?;
+
+    // 服务端按玩家名匹配时不区分大小写
+    if db
+        .list_access(server_config.id)
+        .await?
+        .iter()
+        .any(|access| {
+            access.list_type == list_type
+                && access.player_name.eq_ignore_ascii_case(&req.player_name)
+        })
+    {
+        bail!("player {} is already in the list", req.player_name);
+    }
+
+    let op_level = match list_type {
+        AccessList::Ops => {
+            let op_level = req.op_level.unwrap_or(DEFAULT_OP_LEVEL);
+            if !(1..=MAX_OP_LEVEL).contains(&op_level) {
+                bail!("op level should be between 1 and {MAX_OP_LEVEL}");
+            }
+            op_level
+        }
+        _ => 0,
+    };
+    let reason = match list_type {
+        AccessList::BannedPlayers => {
+            if req
+                .reason
+                .as_ref()
+                .is_some_and(|v| v.len() > BAN_REASON_MAX_LEN || v.contains(['\r', '\n']))
+            {
+                bail!("invalid ban reason");
+            }
+            req.reason
+        }
+        _ => None,
+    };
+
+    let player_uuid = if server_config.properties.online_mode.unwrap_or(true) {
+        resolve_player_uuid(&MojangResolver::new(client), &req.player_name).await
+    } else {
+        resolve_player_uuid(&OfflineResolver, &req.player_name).await
+    };
+
+    let mut access = ServerConfigAccess {
+        server_config_id: server_config.id,
+        list_type,
+        player_name: req.player_name,
+        player_uuid,
+        op_level,
+        reason,
+        ..Default::default()
+    };
+    db.create_access(&mut access).await?;
+    let access = db
+        .get_access_by_id(access.id)
+        .await?
+        .ok_or(anyhow!("access not found"))?;
+
+    sync_running_server(manager, &access, true);
+
+    Ok(AddServerConfigAccessResponse {
+        access: Some(access.into()),
+    })
+}
+
+pub async fn list_server_config_access<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    req: ListServerConfigAccessRequest,
+) -> Result<ListServerConfigAccessResponse>
+where
+    for<'db> Context<'db, DB>: AccessRepository,
+{
+    let accesses = db
+        .list_access(req.server_config_id)
+        .await?
+        .into_iter()
+        .map(ServerConfigAccess::into)
+        .collect();
+
+    Ok(ListServerConfigAccessResponse { accesses })
+}
+
+pub async fn delete_server_config_access<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    manager: &Manager,
+    req: DeleteServerConfigAccessRequest,
+) -> Result<DeleteServerConfigAccessResponse>
+where
+    for<'db> Context<'db, DB>: AccessRepository,
+{
+    let access = db
+        .get_access_by_id(req.id)
+        .await?
+        .ok_or(anyhow!("access not found"))?;
+
+    db.delete_access(access.id).await?;
+    sync_running_server(manager, &access, false);
+
+    Ok(DeleteServerConfigAccessResponse {})
+}
+
+// 启动时按数据库中的名单覆盖服务端生成的文件
+pub(crate) async fn write_access_files<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    root: &str,
+    server_config_id: u64,
+) -> Result<()>
+where
+    for<'db> Context<'db, DB>: AccessRepository,
+{
+    let accesses = db.list_access(server_config_id).await?;
+    let of_list = |list_type| {
+        accesses
+            .iter()
+            .filter(move |access| access.list_type == list_type)
+    };
+
+    let whitelist = of_list(AccessList::Whitelist)
+        .map(|access| WhitelistEntry {
+            uuid: &access.player_uuid,
+            name: &access.player_name,
+        })
+        .collect::<Vec<_>>();
+    let ops = of_list(AccessList::Ops)
+        .map(|access| OpEntry {
+            uuid: &access.player_uuid,
+            name: &access.player_name,
+            level: access.op_level,
+            bypasses_player_limit: false,
+        })
+        .collect::<Vec<_>>();
+    let banned_players = of_list(AccessList::BannedPlayers)
+        .map(|access| BannedPlayerEntry {
+            uuid: &access.player_uuid,
+            name: &access.player_name,
+            created: access
+                .create_time
+                .format("%Y-%m-%d %H:%M:%S %z")
+                .to_string(),
+            source: "Server",
+            expires: "forever",
+            reason: access.reason.as_deref().unwrap_or("Banned by an operator."),
+        })
+        .collect::<Vec<_>>();
+
+    let root = Path::new(root);
+    tokio::fs::write(
+        root.join("whitelist.json"),
+        serde_json::to_vec_pretty(&whitelist)?,
+    )
+    .await?;
+    tokio::fs::write(root.join("ops.json"), serde_json::to_vec_pretty(&ops)?).await?;
+    tokio::fs::write(
+        root.join("banned-players.json"),
+        serde_json::to_vec_pretty(&banned_players)?,
+    )
+    .await?;
+
+    Ok(())
+}
+
+// 运行中的服务器通过控制台命令同步，启动中的服务器会在就绪后执行
+// 同步失败时名单文件会在下次启动时重新写入
+fn sync_running_server(manager: &Manager, access: &ServerConfigAccess, added: bool) {
+    let name = &access.player_name;
+    // op命令使用服务器的op-permission-level，自定义等级在下次启动时生效
+    let command = match (access.list_type, added) {
+        (AccessList::Whitelist, true) => format!("whitelist add {name}"),
+        (AccessList::Whitelist, false) => format!("whitelist remove {name}"),
+        (AccessList::Ops, true) => format!("op {name}"),
+        (AccessList::Ops, false) => format!("deop {name}"),
+        (AccessList::BannedPlayers, true) => match &access.reason {
+            Some(reason) => format!("ban {name} {reason}"),
+            None => format!("ban {name}"),
+        },
+        (AccessList::BannedPlayers, false) => format!("pardon {name}"),
+    };
+
+    let manager = manager.clone();
+    let id = access.server_config_id;
+    tokio::spawn(async move {
+        if !manager.is_running(id).await {
+            return;
+        }
+
+        info!("[{id}] syncing access list: {command}");
+        if let Err(e) = manager.send_command(id, command).await {
+            warn!("[{id}] failed to sync access list: {e:?}");
+        }
+    });
+}
+
+fn validate_player_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > PLAYER_NAME_MAX_LEN
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        bail!("invalid player name");
+    }
+
+    Ok(())
+}
+
+impl From<common::tonic_idl_gen::AccessList> for AccessList {
+    fn from(value: common::tonic_idl_gen::AccessList) -> Self {
+        match value {
+            common::tonic_idl_gen::AccessList::Whitelist => AccessList::Whitelist,
+            common::tonic_idl_gen::AccessList::Ops => AccessList::Ops,
+            common::tonic_idl_gen::AccessList::BannedPlayers => AccessList::BannedPlayers,
+        }
+    }
+}
+
+impl From<AccessList> for common::tonic_idl_gen::AccessList {
+    fn from(value: AccessList) -> Self {
+        match value {
+            AccessList::Whitelist => common::tonic_idl_gen::AccessList::Whitelist,
+            AccessList::Ops => common::tonic_idl_gen::AccessList::Ops,
+            AccessList::BannedPlayers => common::tonic_idl_gen::AccessList::BannedPlayers,
+        }
+    }
+}
+
+impl From<ServerConfigAccess> for common::tonic_idl_gen::ServerConfigAccess {
+    fn from(value: ServerConfigAccess) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            list: common::tonic_idl_gen::AccessList::from(value.list_type) as i32,
+            player_name: value.player_name,
+            player_uuid: value.player_uuid,
+            op_level: value.op_level,
+            reason: value.reason,
+            create_time: value.create_time.timestamp(),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use common::tonic_idl_gen::{
+    AddServerConfigAddonRequest, AddServerConfigAddonResponse, DeleteServerConfigAddonRequest,
+    DeleteServerConfigAddonResponse, ListServerConfigAddonRequest, ListServerConfigAddonResponse,
+};
+use const_format::concatcp;
+use server_common::{
+    db::context::{Context, ContextRef},
+    external_api::aliyun::oss::{HttpOssClient, RUSTWEB_PREFIX},
+};
+use sqlx::Database;
+
+use crate::dao::{
+    addon::{AddonRepository, ServerConfigAddon},
+    server_config::{ServerConfigRepository, ServerFlavor},
+};
+
+const ADDON_URI_PREFIX: &str = concatcp!(RUSTWEB_PREFIX, "mc/addon/");
+const ADDON_NAME_MAX_LEN: usize = 128;
+
+pub async fn add_server_config_addon<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: HttpOssClient<'_, '_>,
+    req: AddServerConfigAddonRequest,
+) -> Result<AddServerConfigAddonResponse>
+where
+    for<'db> Context<'db, DB>: AddonRepository + ServerConfigRepository,
+{
+    validate_addon_name(&req.name)?;
+
+    let server_config = db
+        .get_server_config_by_id(req.server_config_id)
+        .await?
+        .ok_or(anyhow!("server config not found"))?;
+    if server_config.flavor == ServerFlavor::Vanilla {
+        bail!("vanilla server does not support addons");
+    }
+
+    // 文件名即服务器目录中的文件名，不允许重复
+    if db
+        .list_addon(server_config.id)
+        .await?
+        .iter()
+        .any(|addon| addon.name == req.name)
+    {
+        bail!("addon {} already exists", req.name);
+    }
+
+    let mut addon = ServerConfigAddon {
+        server_config_id: server_config.id,
+        uri: format!(
+            "{ADDON_URI_PREFIX}{}/{}-{}",
+            server_config.id,
+            Utc::now().timestamp_millis(),
+            req.name
+        ),
+        name: req.name,
+        ..Default::default()
+    };
+    oss_client.copy_object(&req.uri, &addon.uri).await?;
+    db.create_addon(&mut addon).await?;
+    let addon = db
+        .get_addon_by_id(addon.id)
+        .await?
+        .ok_or(anyhow!("addon not found"))?;
+
+    Ok(AddServerConfigAddonResponse {
+        addon: Some(addon.into()),
+    })
+}
+
+pub async fn list_server_config_addon<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    req: ListServerConfigAddonRequest,
+) -> Result<ListServerConfigAddonResponse>
+where
+    for<'db> Context<'db, DB>: AddonRepository,
+{
+    let addons = db
+        .list_addon(req.server_config_id)
+        .await?
+        .into_iter()
+        .map(ServerConfigAddon::into)
+        .collect();
+
+    Ok(ListServerConfigAddonResponse { addons })
+}
+
+// 运行中的服务器使用本地文件，下次启动时才会从目录中移除
+pub async fn delete_server_config_addon<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: HttpOssClient<'_, '_>,
+    req: DeleteServerConfigAddonRequest,
+) -> Result<DeleteServerConfigAddonResponse>
+where
+    for<'db> Context<'db, DB>: AddonRepository,
+{
+    let addon = db
+        .get_addon_by_id(req.id)
+        .await?
+        .ok_or(anyhow!("addon not found"))?;
+
+    delete_addon(db, &oss_client, &addon).await?;
+
+    Ok(DeleteServerConfigAddonResponse {})
+}
+
+pub async fn delete_all_addon<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: &HttpOssClient<'_, '_>,
+    server_config_id: u64,
+) -> Result<()>
+where
+    for<'db> Context<'db, DB>: AddonRepository,
+{
+    for addon in db.list_addon(server_config_id).await? {
+        delete_addon(db, oss_client, &addon).await?;
+    }
+
+    Ok(())
+}
+
+async fn delete_addon<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: &HttpOssClient<'_, '_>,
+    addon: &ServerConfigAddon,
+) -> Result<()>
+where
+    for<'db> Context<'db, DB>: AddonRepository,
+{
+    oss_client.delete_object(&addon.uri).await?;
+    db.delete_addon(addon.id).await?;
+
+    Ok(())
+}
+
+// 文件名会直接写入服务器目录，只允许简单的jar文件名
+fn validate_addon_name(name: &str) -> Result<()> {
+    if name.len() > ADDON_NAME_MAX_LEN
+        || name.starts_with('.')
+        || !name.ends_with(".jar")
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'))
+    {
+        bail!("invalid addon name: {name}");
+    }
+
+    Ok(())
+}
+
+impl From<ServerConfigAddon> for common::tonic_idl_gen::ServerConfigAddon {
+    fn from(value: ServerConfigAddon) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            name: value.name,
+            create_time: value.create_time.timestamp(),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use common::tonic_idl_gen::{
+    DeleteServerConfigBackupRequest, DeleteServerConfigBackupResponse,
+    ListServerConfigBackupRequest, ListServerConfigBackupResponse,
+    RestoreServerConfigBackupRequest, RestoreServerConfigBackupResponse,
+};
+use const_format::concatcp;
+use rand::{distributions::Alphanumeric, Rng};
+use server_common::{
+    db::context::{Context, ContextRef},
+    external_api::aliyun::oss::{HttpOssClient, RUSTWEB_PREFIX},
+};
+use sqlx::Database;
+use tokio::fs::File;
+use tokio_util::io::ReaderStream;
+use tracing::info;
+
+use crate::{
+    dao::{
+        backup::{BackupRepository, ListBackupParameters, ServerConfigBackup},
+        server_config::{ServerConfig, ServerConfigRepository, UpdateServerConfig},
+    },
+    process::manager::Manager,
+};
+
+use super::server_config::permanent_world_uri;
+
+const BACKUP_URI_PREFIX: &str = concatcp!(RUSTWEB_PREFIX, "mc/backup/");
+// 每个服务器只保留最近的备份
+const BACKUP_KEEP_COUNT: u64 = 10;
+// 较大的存档分片上传
+const MULTIPART_THRESHOLD: u64 = 64 * 1024 * 1024;
+const MULTIPART_PART_SIZE: usize = 16 * 1024 * 1024;
+const BACKUP_KEY_SUFFIX_LEN: usize = 8;
+
+pub async fn upload_world_backup<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: HttpOssClient<'_, '_>,
+    server_config: &ServerConfig,
+    zip_path: &str,
+) -> Result<()>
+where
+    for<'db> Context<'db, DB>: BackupRepository + ServerConfigRepository,
+{
+    // 删除配置时会停止服务器，此时不再保留备份
+    if db
+        .get_server_config_by_id(server_config.id)
+        .await?
+        .is_none()
+    {
+        info!("server {} is deleted, skip backup", server_config.id);
+        return Ok(());
+    }
+
+    let file = File::open(zip_path).await?;
+    let size = file.metadata().await?.len();
+    let mut backup = ServerConfigBackup {
+        server_config_id: server_config.id,
+        uri: format!(
+            "{BACKUP_URI_PREFIX}{}/{}-{}.zip",
+            server_config.id,
+            Utc::now().format("%Y%m%d%H%M%S"),
+            backup_key_suffix()
+        ),
+        size,
+        ..Default::default()
+    };
+
+    let body = ReaderStream::new(file);
+    if size > MULTIPART_THRESHOLD {
+        oss_client
+            .put_object_multipart(&backup.uri, "application/zip", body, MULTIPART_PART_SIZE)
+            .await?;
+    } else {
+        oss_client
+            .put_object(&backup.uri, "application/zip", size, body)
+            .await?;
+    }
+    db.create_backup(&mut backup).await?;
+    info!(
+        "server {} backup uploaded: {}, size: {}",
+        server_config.id, backup.uri, backup.size
+    );
+
+    // 清理过期备份
+    let expired_backups = db
+        .list_backup(&ListBackupParameters {
+            server_config_id: server_config.id,
+            offset: BACKUP_KEEP_COUNT,
+            limit: u64::MAX,
+        })
+        .await?;
+    for backup in expired_backups {
+        delete_backup(db, &oss_client, &backup).await?;
+    }
+
+    Ok(())
+}
+
+// 停止时的备份可能与定时备份在同一秒内完成，加随机后缀避免覆盖
+fn backup_key_suffix() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(BACKUP_KEY_SUFFIX_LEN)
+        .map(char::from)
+        .collect()
+}
+
+pub async fn list_server_config_backup<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    req: ListServerConfigBackupRequest,
+) -> Result<ListServerConfigBackupResponse>
+where
+    for<'db> Context<'db, DB>: BackupRepository,
+{
+    let params = ListBackupParameters {
+        server_config_id: req.server_config_id,
+        offset: req.offset,
+        limit: req.count,
+    };
+
+    let backups = db
+        .list_backup(&params)
+        .await?
+        .into_iter()
+        .map(ServerConfigBackup::into)
+        .collect();
+    let count = db.count_backup(&params).await?;
+
+    Ok(ListServerConfigBackupResponse {
+        total: count,
+        backups,
+    })
+}
+
+pub async fn restore_server_config_backup<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: HttpOssClient<'_, '_>,
+    manager: &Manager,
+    req: RestoreServerConfigBackupRequest,
+) -> Result<RestoreServerConfigBackupResponse>
+where
+    for<'db> Context<'db, DB>: BackupRepository + ServerConfigRepository,
+{
+    let backup = db
+        .get_backup_by_id(req.id)
+        .await?
+        .ok_or(anyhow!("backup not found"))?;
+    let server_config = db
+        .get_server_config_by_id(backup.server_config_id)
+        .await?
+        .ok_or(anyhow!("server config not found"))?;
+
+    if manager.is_running(server_config.id).await {
+        bail!("server config is running");
+    }
+
+    // 替换服务器存档，并清理本地缓存使下次启动时重新下载
+    let world_uri = permanent_world_uri(server_config.id);
+    oss_client.copy_object(&backup.uri, &world_uri).await?;
+    db.update_server_config(
+        server_config.id,
+        &[UpdateServerConfig::WorldUri(Some(&world_uri))],
+    )
+    .await?;
+    if let Some(old_world_uri) = &server_config.world_uri {
+        oss_client.delete_object(old_world_uri).await?;
+    }
+    manager.clean_world_cache(&server_config).await?;
+
+    Ok(RestoreServerConfigBackupResponse {})
+}
+
+pub async fn delete_server_config_backup<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: HttpOssClient<'_, '_>,
+    req: DeleteServerConfigBackupRequest,
+) -> Result<DeleteServerConfigBackupResponse>
+where
+    for<'db> Context<'db, DB>: BackupRepository,
+{
+    let backup = db
+        .get_backup_by_id(req.id)
+        .await?
+        .ok_or(anyhow!("backup not found"))?;
+
+    delete_backup(db, &oss_client, &backup).await?;
+
+    Ok(DeleteServerConfigBackupResponse {})
+}
+
+pub async fn delete_all_backup<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: &HttpOssClient<'_, '_>,
+    server_config_id: u64,
+) -> Result<()>
+where
+    for<'db> Context<'db, DB>: BackupRepository,
+{
+    let backups = db
+        .list_backup(&ListBackupParameters {
+            server_config_id,
+            offset: 0,
+            limit: u64::MAX,
+        })
+        .await?;
+
+    for backup in backups {
+        delete_backup(db, oss_client, &backup).await?;
+    }
+
+    Ok(())
+}
+
+async fn delete_backup<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: &HttpOssClient<'_, '_>,
+    backup: &ServerConfigBackup,
+) -> Result<()>
+where
+    for<'db> Context<'db, DB>: BackupRepository,
+{
+    oss_client.delete_object(&backup.uri).await?;
+    db.delete_backup(backup.id).await?;
+
+    Ok(())
+}
+
+impl From<ServerConfigBackup> for common::tonic_idl_gen::ServerConfigBackup {
+    fn from(value: ServerConfigBackup) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            size: value.size,
+            create_time: value.create_time.timestamp(),
+        }
+    }
+}
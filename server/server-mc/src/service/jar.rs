@@ -0,0 +1,419 @@
+use std::{future::Future, path::Path};
+
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use server_common::db::context::Context;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use sqlx::{MySql, Pool};
+use tokio::{fs::File, io::AsyncReadExt};
+
+use crate::dao::{server_config::ServerFlavor, version::VersionRepository};
+
+const PAPER_API_BASE: &str = "https://api.papermc.io";
+const FABRIC_META_BASE: &str = "https://meta.fabricmc.net";
+
+// 根据服务端类型和版本号获取服务端程序的下载地址和校验信息
+pub trait JarResolver {
+    fn resolve_jar(&self, version: &str) -> impl Future<Output = Result<ServerJar>> + Send;
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerJar {
+    pub url: String,
+    pub checksum: Option<JarChecksum>,
+}
+
+pub async fn resolve_server_jar(
+    db: &Pool<MySql>,
+    client: &Client,
+    flavor: ServerFlavor,
+    version: &str,
+) -> Result<ServerJar> {
+    match flavor {
+        ServerFlavor::Vanilla => VanillaResolver { db }.resolve_jar(version).await,
+        ServerFlavor::Paper => {
+            PaperResolver::new(client, PAPER_API_BASE)
+                .resolve_jar(version)
+                .await
+        }
+        ServerFlavor::Fabric => {
+            FabricResolver::new(client, FABRIC_META_BASE)
+                .resolve_jar(version)
+                .await
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JarChecksum {
+    // 原版manifest提供sha1和文件大小
+    Sha1 { sha1: String, size: u64 },
+    // paper的构建信息只提供sha256
+    Sha256(String),
+}
+
+// 缓存的服务端程序只能用数据库中原版manifest的校验信息检查，paper在下载时校验
+pub async fn server_jar_checksum(
+    db: &Pool<MySql>,
+    flavor: ServerFlavor,
+    version: &str,
+) -> Result<Option<JarChecksum>> {
+    if flavor != ServerFlavor::Vanilla {
+        return Ok(None);
+    }
+
+    let version = Context::PoolRef(db)
+        .get_version_by_mcid(version)
+        .await?
+        .ok_or(anyhow!("version is not available"))?;
+    if version.server_sha1.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(JarChecksum::Sha1 {
+        sha1: version.server_sha1,
+        size: version.server_size,
+    }))
+}
+
+pub async fn verify_jar(path: impl AsRef<Path>, checksum: &JarChecksum) -> Result<bool> {
+    match checksum {
+        JarChecksum::Sha1 { sha1, size } => {
+            if File::open(&path).await?.metadata().await?.len() != *size {
+                return Ok(false);
+            }
+
+            Ok(file_sha1(path).await?.eq_ignore_ascii_case(sha1))
+        }
+        JarChecksum::Sha256(sha256) => Ok(file_digest::<Sha256>(path)
+            .await?
+            .eq_ignore_ascii_case(sha256)),
+    }
+}
+
+// 十六进制小写
+pub async fn file_sha1(path: impl AsRef<Path>) -> Result<String> {
+    file_digest::<Sha1>(path).await
+}
+
+async fn file_digest<D: Digest>(path: impl AsRef<Path>) -> Result<String> {
+    let mut file = File::open(path).await?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    let sha1 = hasher
+        .finalize()
+        .iter()
+        .map(|n| format!("{n:02x}"))
+        .collect::<String>();
+
+    Ok(sha1)
+}
+
+// 原版服务端地址在同步版本时已从manifest写入数据库
+pub struct VanillaResolver<'a> {
+    db: &'a Pool<MySql>,
+}
+
+impl JarResolver for VanillaResolver<'_> {
+    async fn resolve_jar(&self, version: &str) -> Result<ServerJar> {
+        let checksum = server_jar_checksum(self.db, ServerFlavor::Vanilla, version).await?;
+        let version = Context::PoolRef(self.db)
+            .get_version_by_mcid(version)
+            .await?
+            .ok_or(anyhow!("version is not available"))?;
+
+        Ok(ServerJar {
+            url: version.server_url,
+            checksum,
+        })
+    }
+}
+
+pub struct PaperResolver<'a> {
+    client: &'a Client,
+    api_base: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperBuilds {
+    builds: Vec<PaperBuild>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperBuild {
+    build: u32,
+    channel: String,
+    downloads: PaperDownloads,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperDownloads {
+    application: PaperDownload,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperDownload {
+    name: String,
+    sha256: Option<String>,
+}
+
+impl<'a> PaperResolver<'a> {
+    pub fn new(client: &'a Client, api_base: &'a str) -> Self {
+        Self { client, api_base }
+    }
+}
+
+impl JarResolver for PaperResolver<'_> {
+    // 优先使用最新的稳定构建
+    async fn resolve_jar(&self, version: &str) -> Result<ServerJar> {
+        let project_url = format!("{}/v2/projects/paper/versions/{version}", self.api_base);
+        let builds: PaperBuilds = self
+            .client
+            .get(format!("{project_url}/builds"))
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|_| anyhow!("paper is not available for version {version}"))?
+            .json()
+            .await?;
+
+        let build = builds
+            .builds
+            .iter()
+            .rev()
+            .find(|build| build.channel == "default")
+            .or(builds.builds.last())
+            .ok_or(anyhow!("paper has no build for version {version}"))?;
+
+        let download = &build.downloads.application;
+        Ok(ServerJar {
+            url: format!(
+                "{project_url}/builds/{}/downloads/{}",
+                build.build, download.name
+            ),
+            checksum: download.sha256.clone().map(JarChecksum::Sha256),
+        })
+    }
+}
+
+pub struct FabricResolver<'a> {
+    client: &'a Client,
+    meta_base: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricLoaderVersion {
+    loader: FabricComponentVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricComponentVersion {
+    version: String,
+    stable: bool,
+}
+
+impl<'a> FabricResolver<'a> {
+    pub fn new(client: &'a Client, meta_base: &'a str) -> Self {
+        Self { client, meta_base }
+    }
+}
+
+impl JarResolver for FabricResolver<'_> {
+    // 服务端启动器会在首次运行时自行下载原版服务端
+    async fn resolve_jar(&self, version: &str) -> Result<ServerJar> {
+        let loaders: Vec<FabricLoaderVersion> = self
+            .client
+            .get(format!("{}/v2/versions/loader/{version}", self.meta_base))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let loader = loaders
+            .iter()
+            .map(|loader| &loader.loader)
+            .find(|loader| loader.stable)
+            .or(loaders.first().map(|loader| &loader.loader))
+            .ok_or(anyhow!("fabric is not available for version {version}"))?;
+
+        let installers: Vec<FabricComponentVersion> = self
+            .client
+            .get(format!("{}/v2/versions/installer", self.meta_base))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let installer = installers
+            .iter()
+            .find(|installer| installer.stable)
+            .or(installers.first())
+            .ok_or(anyhow!("fabric installer is not available"))?;
+
+        // fabric meta不提供校验信息
+        Ok(ServerJar {
+            url: format!(
+                "{}/v2/versions/loader/{version}/{}/{}/server/jar",
+                self.meta_base, loader.version, installer.version
+            ),
+            checksum: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    // answers GET requests with the canned json of the path, unknown paths get 404
+    async fn serve_json(routes: Vec<(&'static str, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+                let mut request_line = String::new();
+                stream.read_line(&mut request_line).await.unwrap();
+                loop {
+                    let mut header = String::new();
+                    stream.read_line(&mut header).await.unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let response = match routes.iter().find(|(route, _)| *route == path) {
+                    Some((_, body)) => format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        .to_owned(),
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        base
+    }
+
+    #[tokio::test]
+    async fn paper_prefers_latest_stable_build() {
+        let base = serve_json(vec![(
+            "/v2/projects/paper/versions/1.21.4/builds",
+            r#"{"builds": [
+                {"build": 10, "channel": "default", "downloads": {"application": {"name": "paper-1.21.4-10.jar", "sha256": "aa"}}},
+                {"build": 11, "channel": "default", "downloads": {"application": {"name": "paper-1.21.4-11.jar", "sha256": "bb"}}},
+                {"build": 12, "channel": "experimental", "downloads": {"application": {"name": "paper-1.21.4-12.jar", "sha256": "cc"}}}
+            ]}"#,
+        )])
+        .await;
+
+        let client = Client::new();
+        let jar = PaperResolver::new(&client, &base)
+            .resolve_jar("1.21.4")
+            .await
+            .unwrap();
+        assert_eq!(
+            jar.url,
+            format!(
+                "{base}/v2/projects/paper/versions/1.21.4/builds/11/downloads/paper-1.21.4-11.jar"
+            )
+        );
+        assert_eq!(jar.checksum, Some(JarChecksum::Sha256("bb".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn paper_falls_back_to_experimental_build() {
+        let base = serve_json(vec![(
+            "/v2/projects/paper/versions/1.21.5/builds",
+            r#"{"builds": [
+                {"build": 3, "channel": "experimental", "downloads": {"application": {"name": "paper-1.21.5-3.jar"}}}
+            ]}"#,
+        )])
+        .await;
+
+        let client = Client::new();
+        let jar = PaperResolver::new(&client, &base)
+            .resolve_jar("1.21.5")
+            .await
+            .unwrap();
+        assert!(jar.url.ends_with("/builds/3/downloads/paper-1.21.5-3.jar"));
+        assert_eq!(jar.checksum, None);
+    }
+
+    #[tokio::test]
+    async fn paper_unknown_version() {
+        let base = serve_json(vec![]).await;
+
+        let client = Client::new();
+        let result = PaperResolver::new(&client, &base)
+            .resolve_jar("0.0.1")
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn fabric_prefers_stable_loader_and_installer() {
+        let base = serve_json(vec![
+            (
+                "/v2/versions/loader/1.21.4",
+                r#"[
+                    {"loader": {"version": "0.17.0-beta.1", "stable": false}},
+                    {"loader": {"version": "0.16.10", "stable": true}}
+                ]"#,
+            ),
+            (
+                "/v2/versions/installer",
+                r#"[
+                    {"version": "1.1.0", "stable": false},
+                    {"version": "1.0.1", "stable": true}
+                ]"#,
+            ),
+        ])
+        .await;
+
+        let client = Client::new();
+        let jar = FabricResolver::new(&client, &base)
+            .resolve_jar("1.21.4")
+            .await
+            .unwrap();
+        assert_eq!(
+            jar.url,
+            format!("{base}/v2/versions/loader/1.21.4/0.16.10/1.0.1/server/jar")
+        );
+        assert_eq!(jar.checksum, None);
+    }
+
+    #[tokio::test]
+    async fn verify_jar_sha256() {
+        let path = std::env::temp_dir().join(format!("verify-jar-{}.jar", std::process::id()));
+        tokio::fs::write(&path, b"abc").await.unwrap();
+
+        let valid = JarChecksum::Sha256(
+            "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD".to_owned(),
+        );
+        let invalid = JarChecksum::Sha256("00".to_owned());
+        assert!(verify_jar(&path, &valid).await.unwrap());
+        assert!(!verify_jar(&path, &invalid).await.unwrap());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -1,3 +1,10 @@
+pub mod access;
+pub mod addon;
+pub mod backup;
+pub mod jar;
+pub mod player;
 pub mod process;
 pub mod server_config;
+pub mod server_run;
+pub mod uuid;
 pub mod version;
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use common::tonic_idl_gen::{
+    ListOnlinePlayersRequest, ListOnlinePlayersResponse, ListPlayerSessionsRequest,
+    ListPlayerSessionsResponse,
+};
+use server_common::db::context::{Context, ContextRef};
+use sqlx::Database;
+
+use crate::{
+    dao::player_session::{ListPlayerSessionParameters, PlayerSession, PlayerSessionRepository},
+    process::{manager::Manager, player::OnlinePlayer},
+};
+
+pub async fn list_online_players(
+    manager: &Manager,
+    req: ListOnlinePlayersRequest,
+) -> Result<ListOnlinePlayersResponse> {
+    let players = manager
+        .list_online_players(req.server_config_id)
+        .await?
+        .into_iter()
+        .map(OnlinePlayer::into)
+        .collect();
+
+    Ok(ListOnlinePlayersResponse { players })
+}
+
+pub async fn list_player_sessions<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    req: ListPlayerSessionsRequest,
+) -> Result<ListPlayerSessionsResponse>
+where
+    for<'db> Context<'db, DB>: PlayerSessionRepository,
+{
+    let params = ListPlayerSessionParameters {
+        server_config_id: req.server_config_id,
+        player_name: req.player_name,
+        offset: req.offset,
+        limit: req.count,
+    };
+
+    let sessions = db
+        .list_player_session(&params)
+        .await?
+        .into_iter()
+        .map(PlayerSession::into)
+        .collect();
+    let count = db.count_player_session(&params).await?;
+
+    Ok(ListPlayerSessionsResponse {
+        total: count,
+        sessions,
+    })
+}
+
+impl From<OnlinePlayer> for common::tonic_idl_gen::OnlinePlayer {
+    fn from(value: OnlinePlayer) -> Self {
+        Self {
+            name: value.name,
+            uuid: value.uuid,
+            join_time: value.join_time.timestamp(),
+        }
+    }
+}
+
+impl From<PlayerSession> for common::tonic_idl_gen::PlayerSession {
+    fn from(value: PlayerSession) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            player_name: value.player_name,
+            player_uuid: value.player_uuid,
+            join_time: value.join_time.timestamp(),
+            leave_time: value.leave_time.map(|time| time.timestamp()),
+        }
+    }
+}
@@ -1,33 +1,61 @@
 use std::{fs::File as StdFile, path::Path};
 
 use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
 use common::tonic_idl_gen::{
-    GetCurrentServerConfigRequest, GetCurrentServerConfigResponse, RunningServerStage,
-    RunningServerStageInfo, RunningServerStatus, StartServerConfigRequest,
-    StartServerConfigResponse, StopServerConfigRequest, StopServerConfigResponse,
+    ListRunningServersRequest, ListRunningServersResponse, RunningServer, RunningServerStage,
+    RunningServerStageInfo, RunningServerStatus, SendServerCommandRequest,
+    SendServerCommandResponse, ServerConsoleLine, ServerListStatus, ServerPlayerSample,
+    StartServerConfigRequest, StartServerConfigResponse, StopServerConfigRequest,
+    StopServerConfigResponse, StreamServerConsoleRequest,
+};
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
 };
-use futures_util::TryStreamExt;
 use reqwest::Client;
 use server_common::{
     db::context::{Context, ContextRef},
     external_api::aliyun::oss::OssClient,
 };
 use sqlx::{Database, MySql, Pool};
-use tokio::{fs::File, io::AsyncWriteExt};
-use tracing::info;
+use tokio::{fs::File, io::AsyncWriteExt, sync::broadcast::error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
 use zip::ZipArchive;
 
+use super::{
+    access::write_access_files,
+    jar::{file_sha1, resolve_server_jar, server_jar_checksum, verify_jar, ServerJar},
+};
 use crate::{
     dao::{
-        server_config::{ServerConfig, ServerConfigRepository},
+        addon::{AddonRepository, ServerConfigAddon},
+        player_session::{PlayerSession, PlayerSessionRepository},
+        server_config::{
+            Difficulty, ServerConfig, ServerConfigRepository, ServerFlavor, ServerProperties,
+            UpdateServerConfig,
+        },
+        server_run::{ServerRun, ServerRunRepository, ServerRunResult},
         version::VersionRepository,
     },
     process::{
+        communicate::is_stop_command,
+        console::ConsoleLine,
         manager::Manager,
-        status::{ProcessStatus, StartingStatus},
+        ping::ServerStatus,
+        rcon::RconConfig,
+        status::{ProcessStatus, ProgressCounter, RunRecord, StartingStatus},
     },
 };
 
+pub type ServerConsoleStream = BoxStream<'static, ServerConsoleLine>;
+
+const RESOURCE_PACK_TMP_NAME: &str = ".download";
+
+// 下载不完整时重新下载的次数
+const DOWNLOAD_SERVER_JAR_ATTEMPTS: u32 = 3;
+
 pub struct ProcessService {
     db: Pool<MySql>,
     oss_client: OssClient,
@@ -45,51 +73,103 @@ impl ProcessService {
 }
 
 impl crate::process::callback::ProcessService for ProcessService {
-    async fn download_server_jar(&self, version: &str, to_path: &str) -> anyhow::Result<()> {
+    async fn java_major_version(&self, version: &str) -> anyhow::Result<u32> {
         let mut db = Context::PoolRef(&self.db);
         let version = db
             .get_version_by_mcid(version)
             .await?
             .ok_or(anyhow!("version is not available"))?;
 
+        Ok(version.java_major_version)
+    }
+
+    async fn download_server_jar(
+        &self,
+        flavor: ServerFlavor,
+        version: &str,
+        to_path: &str,
+        progress: &ProgressCounter,
+    ) -> anyhow::Result<()> {
+        let ServerJar { url, checksum } =
+            resolve_server_jar(&self.db, &self.client, flavor, version).await?;
         let tmp_path = format!("{to_path}.download");
-        let mut local_file = File::create(&tmp_path).await?;
-        let mut bytes = self
-            .client
-            .get(version.server_url)
-            .send()
-            .await?
-            .bytes_stream();
 
-        while let Some(chunk) = bytes.try_next().await? {
-            local_file.write_all(&chunk).await?;
+        for attempt in 1..=DOWNLOAD_SERVER_JAR_ATTEMPTS {
+            info!("downloading {flavor} server {version} from {url}, attempt {attempt}");
+            let mut local_file = File::create(&tmp_path).await?;
+            let response = self.client.get(&url).send().await?.error_for_status()?;
+            progress.set_bytes_total(response.content_length());
+            progress.reset_bytes();
+            let mut bytes = response.bytes_stream();
+
+            while let Some(chunk) = bytes.try_next().await? {
+                local_file.write_all(&chunk).await?;
+                progress.add_bytes(chunk.len() as u64);
+            }
+            local_file.flush().await?;
+
+            // 校验通过后才放到缓存位置，避免缓存损坏的文件
+            match &checksum {
+                Some(checksum) if !verify_jar(&tmp_path, checksum).await? => {
+                    warn!("{flavor} server {version} checksum mismatch, attempt {attempt}");
+                }
+                _ => {
+                    tokio::fs::rename(&tmp_path, to_path).await?;
+                    return Ok(());
+                }
+            }
         }
 
-        tokio::fs::rename(tmp_path, to_path).await?;
+        tokio::fs::remove_file(&tmp_path).await?;
+        bail!("{flavor} server {version} checksum mismatch after {DOWNLOAD_SERVER_JAR_ATTEMPTS} attempts")
+    }
 
-        Ok(())
+    async fn verify_server_jar(
+        &self,
+        flavor: ServerFlavor,
+        version: &str,
+        path: &str,
+    ) -> anyhow::Result<bool> {
+        match server_jar_checksum(&self.db, flavor, version).await? {
+            Some(checksum) => verify_jar(path, &checksum).await,
+            None => Ok(true),
+        }
     }
 
-    async fn download_world(&self, uri: &str, to_path: &str) -> anyhow::Result<()> {
+    async fn download_world(
+        &self,
+        uri: &str,
+        to_path: &str,
+        progress: &ProgressCounter,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<()> {
         let tmp_path = format!("{to_path}.zip");
         let mut local_file = File::create(&tmp_path).await?;
 
         let client = self.oss_client.with_http(&self.client);
-        let mut bytes = client.get_object(uri).await?;
+        let (length, mut bytes) = client.get_object_with_length(uri).await?;
+        progress.set_bytes_total(length);
 
         while let Some(chunk) = bytes.try_next().await? {
             local_file.write_all(&chunk).await?;
+            progress.add_bytes(chunk.len() as u64);
         }
 
         // extract world data
         let tmp_folder = format!("{to_path}.tmp");
         let final_folder = to_path.to_string();
+        let progress = progress.clone();
+        let cancel = cancel.clone();
         tokio::task::spawn_blocking(move || -> Result<()> {
             let zip_file = StdFile::open(tmp_path)?;
             let mut archive = ZipArchive::new(zip_file)?;
             let target_path = Path::new(&tmp_folder);
+            progress.set_entries_total(Some(archive.len() as u64));
 
             for i in 0..archive.len() {
+                if cancel.is_cancelled() {
+                    bail!("world extraction cancelled");
+                }
                 let mut file = archive.by_index(i)?;
 
                 // 处理文件名编码（支持中文）
@@ -115,6 +195,7 @@ impl crate::process::callback::ProcessService for ProcessService {
                     let mut out_file = StdFile::create(&normalized_name)?;
                     std::io::copy(&mut file, &mut out_file)?;
                 }
+                progress.add_entry();
             }
 
             std::fs::rename(tmp_folder, final_folder)?;
@@ -125,11 +206,72 @@ impl crate::process::callback::ProcessService for ProcessService {
         Ok(())
     }
 
+    async fn list_addons(&self, server_config_id: u64) -> Result<Vec<ServerConfigAddon>> {
+        Context::PoolRef(&self.db)
+            .list_addon(server_config_id)
+            .await
+    }
+
+    async fn download_addon(&self, uri: &str, to_path: &str) -> Result<()> {
+        let tmp_path = format!("{to_path}.download");
+        let mut local_file = File::create(&tmp_path).await?;
+
+        let client = self.oss_client.with_http(&self.client);
+        let mut bytes = client.get_object(uri).await?;
+
+        while let Some(chunk) = bytes.try_next().await? {
+            local_file.write_all(&chunk).await?;
+        }
+        local_file.flush().await?;
+
+        tokio::fs::rename(tmp_path, to_path).await?;
+
+        Ok(())
+    }
+
+    async fn download_resource_pack(&self, uri: &str, to_dir: &str) -> anyhow::Result<String> {
+        // 资源包地址带有时间戳，文件名相同时内容不变
+        let file_name = Path::new(uri)
+            .file_name()
+            .ok_or(anyhow!("invalid resource pack uri: {uri}"))?;
+        let path = Path::new(to_dir).join(file_name);
+        tokio::fs::create_dir_all(to_dir).await?;
+
+        if !path.exists() {
+            info!("downloading resource pack {uri}");
+            let tmp_path = Path::new(to_dir).join(RESOURCE_PACK_TMP_NAME);
+            let mut local_file = File::create(&tmp_path).await?;
+
+            let client = self.oss_client.with_http(&self.client);
+            let mut bytes = client.get_object(uri).await?;
+
+            while let Some(chunk) = bytes.try_next().await? {
+                local_file.write_all(&chunk).await?;
+            }
+            local_file.flush().await?;
+
+            tokio::fs::rename(&tmp_path, &path).await?;
+        }
+
+        // 删除旧版本的资源包
+        let mut entries = tokio::fs::read_dir(to_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_name() != file_name {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+        }
+
+        file_sha1(&path).await
+    }
+
     async fn initialize_config_files(
         &self,
         root: &str,
         world_dir_name: &str,
         server_config: &ServerConfig,
+        port: u16,
+        rcon: &RconConfig,
+        resource_pack_sha1: Option<&str>,
     ) -> anyhow::Result<()> {
         // eula.txt
         {
@@ -139,44 +281,167 @@ impl crate::process::callback::ProcessService for ProcessService {
                 file.write_all(r#"eula=true"#.as_bytes()).await?;
             }
         }
+        // whitelist.json, ops.json, banned-players.json
+        write_access_files(&mut Context::PoolRef(&self.db), root, server_config.id).await?;
         // server.properties
         {
             let public_server_host = std::env::var("RUSTWEB_MC_HOST")?;
 
+            // 用户配置覆盖默认值，端口、存档等由服务管理的项不允许配置
+            let properties = server_config
+                .properties
+                .merge(&default_server_properties())
+                .entries()
+                .into_iter()
+                .chain([
+                    ("level-name", world_dir_name.to_owned()),
+                    ("server-port", port.to_string()),
+                    ("motd", server_config.motd.clone()),
+                    // 原版服务端不能单独设置rcon的监听地址，由NetworkPolicy拦截外部对rcon端口的访问，
+                    // 只能从pod内通过127.0.0.1连接，密码每次启动重新生成
+                    ("enable-rcon", true.to_string()),
+                    ("rcon.port", rcon.port.to_string()),
+                    ("rcon.password", rcon.password.clone()),
+                    ("broadcast-rcon-to-ops", false.to_string()),
+                ]);
+
             let path = Path::new(root).join("server.properties");
             let mut file = File::create(path).await?;
-            file.write_all("difficulty=hard\n".as_bytes()).await?;
-            file.write_all("view-distance=16\n".as_bytes()).await?;
-            file.write_all(format!("level-name={world_dir_name}\n").as_bytes())
-                .await?;
-            file.write_all("enable-command-block=true\n".as_bytes())
-                .await?;
-            file.write_all(format!("motd={}", server_config.motd).as_bytes())
-                .await?;
-            if server_config.resource_uri.is_some() {
-                file.write_all(
-                    format!("resource-pack=http://{public_server_host}/api/mc/resource-pack?id=\n")
-                        .as_bytes(),
-                )
-                .await?;
+            for (key, value) in properties {
+                file.write_all(format!("{key}={}\n", escape_property_value(&value)).as_bytes())
+                    .await?;
+            }
+            // 地址中带上sha1，资源包更新后客户端不会使用旧的缓存
+            if let Some(sha1) = resource_pack_sha1 {
+                let resource_pack = [
+                    (
+                        "resource-pack",
+                        format!(
+                            "http://{public_server_host}/api/mc/resource-pack?id={}&hash={sha1}",
+                            server_config.id
+                        ),
+                    ),
+                    ("resource-pack-sha1", sha1.to_owned()),
+                ];
+                for (key, value) in resource_pack {
+                    file.write_all(format!("{key}={}\n", escape_property_value(&value)).as_bytes())
+                        .await?;
+                }
             }
         }
         Ok(())
     }
 
-    async fn server_started(&self) -> () {
-        info!("server started");
+    async fn backup_world(&self, server_config: &ServerConfig, zip_path: &str) -> Result<()> {
+        super::backup::upload_world_backup(
+            &mut Context::PoolRef(&self.db),
+            self.oss_client.with_http(&self.client),
+            server_config,
+            zip_path,
+        )
+        .await
+    }
+
+    async fn player_joined(
+        &self,
+        server_config: &ServerConfig,
+        name: &str,
+        uuid: &str,
+    ) -> Result<u64> {
+        let mut session = PlayerSession {
+            server_config_id: server_config.id,
+            player_name: name.to_owned(),
+            player_uuid: uuid.to_owned(),
+            join_time: Utc::now(),
+            ..Default::default()
+        };
+        Context::PoolRef(&self.db)
+            .create_player_session(&mut session)
+            .await?;
+
+        Ok(session.id)
     }
 
-    async fn server_stop(&self) -> () {
-        info!("server stopped");
+    async fn player_left(&self, session_id: u64) -> Result<()> {
+        Context::PoolRef(&self.db)
+            .finish_player_session(session_id, Utc::now())
+            .await
     }
 
-    async fn stdout_line(&self, line: &str) -> () {
-        info!("stdout: {}", line);
+    async fn run_started(
+        &self,
+        server_config: &ServerConfig,
+        start_time: DateTime<Utc>,
+    ) -> Result<u64> {
+        let mut run = ServerRun {
+            server_config_id: server_config.id,
+            start_time,
+            stop_time: start_time,
+            result: ServerRunResult::Running,
+            ..Default::default()
+        };
+        Context::PoolRef(&self.db)
+            .create_server_run(&mut run)
+            .await?;
+
+        Ok(run.id)
+    }
+
+    async fn record_run(
+        &self,
+        server_config: &ServerConfig,
+        run_id: Option<u64>,
+        record: &RunRecord,
+    ) -> Result<()> {
+        let mut run = ServerRun {
+            server_config_id: server_config.id,
+            ..record.into()
+        };
+        // 启动时没能写入记录的，结束时补写
+        match run_id {
+            Some(id) => {
+                run.id = id;
+                Context::PoolRef(&self.db).finish_server_run(&run).await
+            }
+            None => Context::PoolRef(&self.db).create_server_run(&mut run).await,
+        }
+    }
+
+    async fn server_started(&self, server_config: &ServerConfig) -> () {
+        info!("server {} started", server_config.id);
+    }
+
+    async fn server_stop(&self, server_config: &ServerConfig) -> () {
+        info!("server {} stopped", server_config.id);
+
+        // 服务器停止或崩溃时不会输出离开信息
+        if let Err(e) = Context::PoolRef(&self.db)
+            .finish_open_player_sessions(server_config.id, Utc::now())
+            .await
+        {
+            warn!("failed to finish player sessions: {e:?}");
+        }
+    }
+
+    async fn stdout_line(&self, server_config: &ServerConfig, line: &str) -> () {
+        info!("stdout[{}]: {}", server_config.id, line);
+    }
+}
+
+fn default_server_properties() -> ServerProperties {
+    ServerProperties {
+        difficulty: Some(Difficulty::Hard),
+        view_distance: Some(16),
+        enable_command_block: Some(true),
+        ..Default::default()
     }
 }
 
+// 换行会被解析为新的配置项，需要转义
+fn escape_property_value(value: &str) -> String {
+    value.replace('\r', "\\r").replace('\n', "\\n")
+}
+
 pub async fn start_server_config<DB: Database>(
     db: ContextRef<'_, '_, DB>,
     manager: &Manager,
@@ -190,54 +455,152 @@ where
     };
 
     manager.start_server_config(server_config).await?;
+    db.update_server_config(req.id, &[UpdateServerConfig::DesiredRunning(true)])
+        .await?;
 
     Ok(StartServerConfigResponse {})
 }
 
-pub async fn stop_server_config(
+pub async fn stop_server_config<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
     manager: &Manager,
-    _req: StopServerConfigRequest,
-) -> Result<StopServerConfigResponse> {
-    manager.stop_server_config().await?;
+    req: StopServerConfigRequest,
+) -> Result<StopServerConfigResponse>
+where
+    for<'db> Context<'db, DB>: ServerConfigRepository,
+{
+    db.update_server_config(req.id, &[UpdateServerConfig::DesiredRunning(false)])
+        .await?;
+    manager.stop_server_config(req.id).await?;
     Ok(StopServerConfigResponse {})
 }
 
-pub async fn get_current_server_config(
+// server-mc重启后恢复之前运行中的服务器，单个服务器启动失败不影响其他服务器
+pub async fn resume_server_configs<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    manager: &Manager,
+) -> Result<()>
+where
+    for<'db> Context<'db, DB>: ServerConfigRepository,
+{
+    for server_config in db.list_desired_running_server_config().await? {
+        let id = server_config.id;
+        info!("resuming server config {id}");
+        if let Err(e) = manager.start_server_config(server_config).await {
+            warn!("failed to resume server config {id}: {e:?}");
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn list_running_servers(
     manager: &Manager,
-    _req: GetCurrentServerConfigRequest,
-) -> Result<GetCurrentServerConfigResponse> {
-    let running_config = manager.running_config().await;
-    let status_map = manager.status_map().await;
-    let current_status = status_map.keys().max().copied();
-    let status_info = status_map
+    _req: ListRunningServersRequest,
+) -> Result<ListRunningServersResponse> {
+    let servers = manager
+        .list_instances()
+        .await
         .into_iter()
-        .map(|(status, info)| RunningServerStageInfo {
-            stage: RunningServerStage::from(status) as i32,
-            enter_time: info.start_time.timestamp(),
-            finish_time: info.end_time.map(|time| time.timestamp()),
-            in_error: info.error.is_some(),
-            error_message: info.error,
+        .map(|instance| {
+            let current_status = instance.status.keys().max().copied();
+            let status_info = instance
+                .status
+                .into_iter()
+                .map(|(status, info)| RunningServerStageInfo {
+                    stage: RunningServerStage::from(status) as i32,
+                    enter_time: info.start_time.timestamp(),
+                    finish_time: info.end_time.map(|time| time.timestamp()),
+                    in_error: info.error.is_some(),
+                    error_message: info.error,
+                    exit_code: info.exit_code,
+                    bytes_done: info.progress.map(|progress| progress.bytes_done),
+                    bytes_total: info.progress.and_then(|progress| progress.bytes_total),
+                    entries_extracted: info.progress.map(|progress| progress.entries_extracted),
+                    entries_total: info.progress.and_then(|progress| progress.entries_total),
+                })
+                .collect();
+
+            RunningServer {
+                config: Some(instance.server_config.into()),
+                status: current_status.map(|current_status| RunningServerStatus {
+                    stage: RunningServerStage::from(current_status) as i32,
+                    stage_info: status_info,
+                }),
+                port: instance.port as u32,
+                list_status: instance.server_status.map(ServerStatus::into),
+                resource_pack_sha1: instance.resource_pack_sha1,
+            }
         })
         .collect();
 
-    Ok(GetCurrentServerConfigResponse {
-        running_config: running_config.map(ServerConfig::into),
-        status: current_status.map(|current_status| RunningServerStatus {
-            stage: RunningServerStage::from(current_status) as i32,
-            stage_info: status_info,
-        }),
-    })
+    Ok(ListRunningServersResponse { servers })
+}
+
+pub async fn stream_server_console(
+    manager: &Manager,
+    req: StreamServerConsoleRequest,
+) -> Result<ServerConsoleStream> {
+    let (history, receiver) = manager.subscribe_console(req.id).await?;
+
+    let new_lines = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(line) => return Some((line, receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("console subscriber lagged, {skipped} lines skipped");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(stream::iter(history)
+        .chain(new_lines)
+        .map(ConsoleLine::into)
+        .boxed())
+}
+
+pub async fn send_server_command<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    manager: &Manager,
+    req: SendServerCommandRequest,
+) -> Result<SendServerCommandResponse>
+where
+    for<'db> Context<'db, DB>: ServerConfigRepository,
+{
+    if req.command.is_empty() || req.command.contains(['\r', '\n']) {
+        bail!("invalid command");
+    }
+
+    let stop = is_stop_command(&req.command);
+    let output = manager.send_command(req.id, req.command).await?;
+
+    // 通过命令停止的服务器与主动停止一致，server-mc重启后不再恢复；命令发送失败时服务器仍在运行，不修改
+    if stop {
+        db.update_server_config(req.id, &[UpdateServerConfig::DesiredRunning(false)])
+            .await?;
+    }
+
+    Ok(SendServerCommandResponse { output })
 }
 
 impl From<ProcessStatus> for RunningServerStage {
     fn from(value: ProcessStatus) -> Self {
         match value {
+            ProcessStatus::Crashed(_) => RunningServerStage::Crashed,
+            ProcessStatus::Starting(StartingStatus::SelectJavaRuntime) => {
+                RunningServerStage::SelectingJava
+            }
             ProcessStatus::Starting(StartingStatus::DownloadServerJar) => {
                 RunningServerStage::PullingServer
             }
             ProcessStatus::Starting(StartingStatus::DownloadWorld) => {
                 RunningServerStage::PullingWorld
             }
+            ProcessStatus::Starting(StartingStatus::InstallAddons) => {
+                RunningServerStage::InstallingAddons
+            }
             ProcessStatus::Starting(StartingStatus::InitializeConfigFile) => {
                 RunningServerStage::InitializingFile
             }
@@ -246,7 +609,38 @@ impl From<ProcessStatus> for RunningServerStage {
             }
             ProcessStatus::Running => RunningServerStage::Running,
             ProcessStatus::Terminating => RunningServerStage::Stopping,
+            ProcessStatus::Cancelled => RunningServerStage::Cancelled,
             ProcessStatus::Terminated => RunningServerStage::Stopped,
         }
     }
 }
+
+impl From<ServerStatus> for ServerListStatus {
+    fn from(value: ServerStatus) -> Self {
+        Self {
+            version: value.version,
+            protocol: value.protocol,
+            online_players: value.online_players,
+            max_players: value.max_players,
+            players: value
+                .players
+                .into_iter()
+                .map(|player| ServerPlayerSample {
+                    name: player.name,
+                    id: player.id,
+                })
+                .collect(),
+            latency_ms: value.latency.as_millis() as u32,
+            update_time: value.update_time.timestamp(),
+        }
+    }
+}
+
+impl From<ConsoleLine> for ServerConsoleLine {
+    fn from(value: ConsoleLine) -> Self {
+        Self {
+            time: value.time.timestamp(),
+            line: value.line,
+        }
+    }
+}
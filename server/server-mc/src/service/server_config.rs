@@ -1,7 +1,9 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
 use common::tonic_idl_gen::{
     CreateServerConfigRequest, CreateServerConfigResponse, DeleteServerConfigRequest,
     DeleteServerConfigResponse, ListServerConfigRequest, ListServerConfigResponse,
+    UpdateServerConfigRequest, UpdateServerConfigResponse,
 };
 use const_format::concatcp;
 use server_common::{
@@ -11,27 +13,52 @@ use server_common::{
 use sqlx::Database;
 
 use crate::{
-    dao::server_config::{
-        ListServerConfigParameters, ServerConfig, ServerConfigRepository, UpdateServerConfig,
+    dao::{
+        access::AccessRepository,
+        addon::AddonRepository,
+        backup::BackupRepository,
+        server_config::{
+            Difficulty, GameMode, JvmOptions, ListServerConfigParameters, RestartPolicy,
+            ServerConfig, ServerConfigRepository, ServerFlavor, ServerProperties,
+            UpdateServerConfig,
+        },
     },
     process::manager::Manager,
 };
 
+use super::{addon::delete_all_addon, backup::delete_all_backup};
+
 const WORLD_URI_PREFIX: &str = concatcp!(RUSTWEB_PREFIX, "mc/world/");
 const RESOURCE_URI_PREFIX: &str = concatcp!(RUSTWEB_PREFIX, "mc/resource/");
 
 pub async fn create_server_config<DB: Database>(
     db: ContextRef<'_, '_, DB>,
     oss_client: HttpOssClient<'_, '_>,
-    req: CreateServerConfigRequest,
+    mut req: CreateServerConfigRequest,
 ) -> Result<CreateServerConfigResponse>
 where
     for<'db> Context<'db, DB>: ServerConfigRepository,
 {
+    let properties: ServerProperties = req
+        .properties
+        .take()
+        .map(TryInto::try_into)
+        .transpose()?
+        .unwrap_or_default();
+    properties.validate()?;
+
+    let jvm_options: JvmOptions = req.jvm_options.take().map(Into::into).unwrap_or_default();
+    jvm_options.validate()?;
+
     let mut server_config = ServerConfig {
+        restart_policy: req.restart_policy().into(),
+        flavor: req.flavor().into(),
         name: req.name,
         mc_version: req.version,
+        restart_max_retries: req.restart_max_retries,
         motd: req.motd,
+        properties,
+        jvm_options,
         ..Default::default()
     };
 
@@ -40,7 +67,7 @@ where
 
     let mut updates = Vec::new();
     if let Some(world_uri) = req.world_uri {
-        let permanent_world_uri = format!("{WORLD_URI_PREFIX}world-{}.zip", server_config.id);
+        let permanent_world_uri = permanent_world_uri(server_config.id);
         oss_client
             .copy_object(&world_uri, &permanent_world_uri)
             .await?;
@@ -51,7 +78,7 @@ where
     }
 
     if let Some(resource_uri) = req.resource_uri {
-        let permanent_resource_uri = format!("{RESOURCE_URI_PREFIX}world-{}.zip", server_config.id);
+        let permanent_resource_uri = permanent_resource_uri(server_config.id);
         oss_client
             .copy_object(&resource_uri, &permanent_resource_uri)
             .await?;
@@ -93,6 +120,110 @@ where
     })
 }
 
+pub async fn update_server_config<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    oss_client: HttpOssClient<'_, '_>,
+    manager: &Manager,
+    mut req: UpdateServerConfigRequest,
+) -> Result<UpdateServerConfigResponse>
+where
+    for<'db> Context<'db, DB>: ServerConfigRepository,
+{
+    let server_config = db
+        .get_server_config_by_id(req.id)
+        .await?
+        .ok_or(anyhow!("server config not found"))?;
+
+    // 运行中的服务器仍在使用旧的版本、存档和资源包
+    if (req.version.is_some()
+        || req.flavor.is_some()
+        || req.world_uri.is_some()
+        || req.resource_uri.is_some())
+        && manager.is_running(server_config.id).await
+    {
+        bail!("server config is running");
+    }
+
+    let properties: Option<ServerProperties> =
+        req.properties.take().map(TryInto::try_into).transpose()?;
+    if let Some(properties) = &properties {
+        properties.validate()?;
+    }
+    let jvm_options: Option<JvmOptions> = req.jvm_options.take().map(Into::into);
+    if let Some(jvm_options) = &jvm_options {
+        jvm_options.validate()?;
+    }
+    let restart_policy: Option<RestartPolicy> =
+        req.restart_policy.map(|_| req.restart_policy().into());
+    let flavor: Option<ServerFlavor> = req.flavor.map(|_| req.flavor().into());
+
+    // 新文件复制到新的地址，更新成功后再删除旧文件
+    let world_uri = match &req.world_uri {
+        Some(uri) => {
+            let world_uri = permanent_world_uri(server_config.id);
+            oss_client.copy_object(uri, &world_uri).await?;
+            Some(world_uri)
+        }
+        None => None,
+    };
+    let resource_uri = match &req.resource_uri {
+        Some(uri) => {
+            let resource_uri = permanent_resource_uri(server_config.id);
+            oss_client.copy_object(uri, &resource_uri).await?;
+            Some(resource_uri)
+        }
+        None => None,
+    };
+
+    let mut updates = Vec::new();
+    if let Some(name) = &req.name {
+        updates.push(UpdateServerConfig::Name(name));
+    }
+    if let Some(version) = &req.version {
+        updates.push(UpdateServerConfig::McVersion(version));
+    }
+    if let Some(flavor) = flavor {
+        updates.push(UpdateServerConfig::Flavor(flavor));
+    }
+    if world_uri.is_some() {
+        updates.push(UpdateServerConfig::WorldUri(world_uri.as_ref()));
+    }
+    if resource_uri.is_some() {
+        updates.push(UpdateServerConfig::ResourceUri(resource_uri.as_ref()));
+    }
+    if let Some(motd) = &req.motd {
+        updates.push(UpdateServerConfig::Motd(motd));
+    }
+    if let Some(restart_policy) = restart_policy {
+        updates.push(UpdateServerConfig::RestartPolicy(restart_policy));
+    }
+    if let Some(restart_max_retries) = req.restart_max_retries {
+        updates.push(UpdateServerConfig::RestartMaxRetries(restart_max_retries));
+    }
+    if let Some(properties) = &properties {
+        updates.push(UpdateServerConfig::Properties(properties));
+    }
+    if let Some(jvm_options) = &jvm_options {
+        updates.push(UpdateServerConfig::JvmOptions(jvm_options));
+    }
+    db.update_server_config(server_config.id, &updates).await?;
+
+    if world_uri.is_some() {
+        if let Some(old_world_uri) = &server_config.world_uri {
+            oss_client.delete_object(old_world_uri).await?;
+        }
+        // 清理本地缓存使下次启动时重新下载
+        manager.clean_world_cache(&server_config).await?;
+    }
+    if resource_uri.is_some() {
+        if let Some(old_resource_uri) = &server_config.resource_uri {
+            oss_client.delete_object(old_resource_uri).await?;
+        }
+    }
+
+    Ok(UpdateServerConfigResponse {})
+}
+
 pub async fn delete_server_config<DB: Database>(
     db: ContextRef<'_, '_, DB>,
     oss_client: HttpOssClient<'_, '_>,
@@ -100,7 +231,8 @@ pub async fn delete_server_config<DB: Database>(
     req: DeleteServerConfigRequest,
 ) -> Result<DeleteServerConfigResponse>
 where
-    for<'db> Context<'db, DB>: ServerConfigRepository,
+    for<'db> Context<'db, DB>:
+        ServerConfigRepository + BackupRepository + AddonRepository + AccessRepository,
 {
     let server_config = db
         .get_server_config_by_id(req.id)
@@ -108,6 +240,9 @@ where
         .ok_or(anyhow!("server config not found"))?;
 
     db.delete_server_config(server_config.id).await?;
+    delete_all_backup(db, &oss_client, server_config.id).await?;
+    delete_all_addon(db, &oss_client, server_config.id).await?;
+    db.delete_access_by_config(server_config.id).await?;
 
     // delete oss reources
     if let Some(world_uri) = &server_config.world_uri {
@@ -119,12 +254,8 @@ where
     }
 
     // clean disk cache
-    if manager
-        .running_config()
-        .await
-        .is_some_and(|server_config| server_config.id == req.id)
-    {
-        manager.stop_server_config().await?;
+    if manager.is_running(req.id).await {
+        manager.stop_server_config(req.id).await?;
     }
 
     manager.clean_world_cache(&server_config).await?;
@@ -132,6 +263,21 @@ where
     Ok(DeleteServerConfigResponse {})
 }
 
+// 地址带上时间戳，替换文件时不会覆盖仍在使用的旧文件
+pub(crate) fn permanent_world_uri(server_config_id: u64) -> String {
+    format!(
+        "{WORLD_URI_PREFIX}world-{server_config_id}-{}.zip",
+        Utc::now().timestamp_millis()
+    )
+}
+
+fn permanent_resource_uri(server_config_id: u64) -> String {
+    format!(
+        "{RESOURCE_URI_PREFIX}world-{server_config_id}-{}.zip",
+        Utc::now().timestamp_millis()
+    )
+}
+
 impl From<ServerConfig> for common::tonic_idl_gen::ServerConfig {
     fn from(value: ServerConfig) -> Self {
         Self {
@@ -141,6 +287,165 @@ impl From<ServerConfig> for common::tonic_idl_gen::ServerConfig {
             world_uri: value.world_uri,
             resource_uri: value.resource_uri,
             motd: value.motd,
+            restart_policy: common::tonic_idl_gen::RestartPolicy::from(value.restart_policy) as i32,
+            flavor: common::tonic_idl_gen::ServerFlavor::from(value.flavor) as i32,
+            restart_max_retries: value.restart_max_retries,
+            properties: Some(value.properties.into()),
+            jvm_options: Some(value.jvm_options.into()),
+        }
+    }
+}
+
+impl From<JvmOptions> for common::tonic_idl_gen::JvmOptions {
+    fn from(value: JvmOptions) -> Self {
+        Self {
+            min_memory_mb: value.min_memory_mb,
+            max_memory_mb: value.max_memory_mb,
+            extra_args: value.extra_args,
+            aikar_flags: value.aikar_flags,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::JvmOptions> for JvmOptions {
+    fn from(value: common::tonic_idl_gen::JvmOptions) -> Self {
+        Self {
+            min_memory_mb: value.min_memory_mb,
+            max_memory_mb: value.max_memory_mb,
+            extra_args: value.extra_args,
+            aikar_flags: value.aikar_flags,
+        }
+    }
+}
+
+impl From<ServerProperties> for common::tonic_idl_gen::ServerProperties {
+    fn from(value: ServerProperties) -> Self {
+        Self {
+            gamemode: value
+                .gamemode
+                .map(|v| common::tonic_idl_gen::GameMode::from(v) as i32),
+            difficulty: value
+                .difficulty
+                .map(|v| common::tonic_idl_gen::Difficulty::from(v) as i32),
+            max_players: value.max_players,
+            pvp: value.pvp,
+            level_seed: value.level_seed,
+            white_list: value.white_list,
+            online_mode: value.online_mode,
+            view_distance: value.view_distance,
+            enable_command_block: value.enable_command_block,
+            require_resource_pack: value.require_resource_pack,
+        }
+    }
+}
+
+impl TryFrom<common::tonic_idl_gen::ServerProperties> for ServerProperties {
+    type Error = anyhow::Error;
+
+    fn try_from(value: common::tonic_idl_gen::ServerProperties) -> Result<Self> {
+        Ok(Self {
+            gamemode: value
+                .gamemode
+                .map(common::tonic_idl_gen::GameMode::try_from)
+                .transpose()?
+                .map(GameMode::from),
+            difficulty: value
+                .difficulty
+                .map(common::tonic_idl_gen::Difficulty::try_from)
+                .transpose()?
+                .map(Difficulty::from),
+            max_players: value.max_players,
+            pvp: value.pvp,
+            level_seed: value.level_seed,
+            white_list: value.white_list,
+            online_mode: value.online_mode,
+            view_distance: value.view_distance,
+            enable_command_block: value.enable_command_block,
+            require_resource_pack: value.require_resource_pack,
+        })
+    }
+}
+
+impl From<common::tonic_idl_gen::GameMode> for GameMode {
+    fn from(value: common::tonic_idl_gen::GameMode) -> Self {
+        match value {
+            common::tonic_idl_gen::GameMode::Survival => GameMode::Survival,
+            common::tonic_idl_gen::GameMode::Creative => GameMode::Creative,
+            common::tonic_idl_gen::GameMode::Adventure => GameMode::Adventure,
+            common::tonic_idl_gen::GameMode::Spectator => GameMode::Spectator,
+        }
+    }
+}
+
+impl From<GameMode> for common::tonic_idl_gen::GameMode {
+    fn from(value: GameMode) -> Self {
+        match value {
+            GameMode::Survival => common::tonic_idl_gen::GameMode::Survival,
+            GameMode::Creative => common::tonic_idl_gen::GameMode::Creative,
+            GameMode::Adventure => common::tonic_idl_gen::GameMode::Adventure,
+            GameMode::Spectator => common::tonic_idl_gen::GameMode::Spectator,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::Difficulty> for Difficulty {
+    fn from(value: common::tonic_idl_gen::Difficulty) -> Self {
+        match value {
+            common::tonic_idl_gen::Difficulty::Peaceful => Difficulty::Peaceful,
+            common::tonic_idl_gen::Difficulty::Easy => Difficulty::Easy,
+            common::tonic_idl_gen::Difficulty::Normal => Difficulty::Normal,
+            common::tonic_idl_gen::Difficulty::Hard => Difficulty::Hard,
+        }
+    }
+}
+
+impl From<Difficulty> for common::tonic_idl_gen::Difficulty {
+    fn from(value: Difficulty) -> Self {
+        match value {
+            Difficulty::Peaceful => common::tonic_idl_gen::Difficulty::Peaceful,
+            Difficulty::Easy => common::tonic_idl_gen::Difficulty::Easy,
+            Difficulty::Normal => common::tonic_idl_gen::Difficulty::Normal,
+            Difficulty::Hard => common::tonic_idl_gen::Difficulty::Hard,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::RestartPolicy> for RestartPolicy {
+    fn from(value: common::tonic_idl_gen::RestartPolicy) -> Self {
+        match value {
+            common::tonic_idl_gen::RestartPolicy::Never => RestartPolicy::Never,
+            common::tonic_idl_gen::RestartPolicy::OnFailure => RestartPolicy::OnFailure,
+            common::tonic_idl_gen::RestartPolicy::Always => RestartPolicy::Always,
+        }
+    }
+}
+
+impl From<RestartPolicy> for common::tonic_idl_gen::RestartPolicy {
+    fn from(value: RestartPolicy) -> Self {
+        match value {
+            RestartPolicy::Never => common::tonic_idl_gen::RestartPolicy::Never,
+            RestartPolicy::OnFailure => common::tonic_idl_gen::RestartPolicy::OnFailure,
+            RestartPolicy::Always => common::tonic_idl_gen::RestartPolicy::Always,
+        }
+    }
+}
+
+impl From<common::tonic_idl_gen::ServerFlavor> for ServerFlavor {
+    fn from(value: common::tonic_idl_gen::ServerFlavor) -> Self {
+        match value {
+            common::tonic_idl_gen::ServerFlavor::Vanilla => ServerFlavor::Vanilla,
+            common::tonic_idl_gen::ServerFlavor::Paper => ServerFlavor::Paper,
+            common::tonic_idl_gen::ServerFlavor::Fabric => ServerFlavor::Fabric,
+        }
+    }
+}
+
+impl From<ServerFlavor> for common::tonic_idl_gen::ServerFlavor {
+    fn from(value: ServerFlavor) -> Self {
+        match value {
+            ServerFlavor::Vanilla => common::tonic_idl_gen::ServerFlavor::Vanilla,
+            ServerFlavor::Paper => common::tonic_idl_gen::ServerFlavor::Paper,
+            ServerFlavor::Fabric => common::tonic_idl_gen::ServerFlavor::Fabric,
         }
     }
 }
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use chrono::Utc;
+use common::tonic_idl_gen::{
+    ListServerRunsRequest, ListServerRunsResponse, RunningServerStage, RunningServerStageInfo,
+};
+use server_common::db::context::{Context, ContextRef};
+use sqlx::Database;
+use tracing::info;
+
+use crate::{
+    dao::server_run::{
+        ListServerRunParameters, ServerRun, ServerRunRepository, ServerRunResult, ServerRunStage,
+        ServerRunStageInfo, ServerRunStages,
+    },
+    process::status::{ProcessStatus, RunRecord, RunResult, StartingStatus},
+};
+
+pub async fn list_server_runs<DB: Database>(
+    db: ContextRef<'_, '_, DB>,
+    req: ListServerRunsRequest,
+) -> Result<ListServerRunsResponse>
+where
+    for<'db> Context<'db, DB>: ServerRunRepository,
+{
+    let params = ListServerRunParameters {
+        server_config_id: req.server_config_id,
+        offset: req.offset,
+        limit: req.count,
+    };
+
+    let runs = db
+        .list_server_run(&params)
+        .await?
+        .into_iter()
+        .map(ServerRun::into)
+        .collect();
+    let count = db.count_server_run(&params).await?;
+
+    Ok(ListServerRunsResponse { total: count, runs })
+}
+
+// 上次退出时仍在运行的记录已经无法得知结束方式，需要在恢复服务器前处理
+pub async fn interrupt_running_server_runs<DB: Database>(db: ContextRef<'_, '_, DB>) -> Result<()>
+where
+    for<'db> Context<'db, DB>: ServerRunRepository,
+{
+    let count = db.interrupt_running_server_runs(Utc::now()).await?;
+    if count > 0 {
+        info!("marked {count} unfinished server runs as interrupted");
+    }
+
+    Ok(())
+}
+
+// 崩溃、取消和终止不属于单次运行的阶段
+fn run_stage(status: ProcessStatus) -> Option<ServerRunStage> {
+    match status {
+        ProcessStatus::Starting(StartingStatus::SelectJavaRuntime) => {
+            Some(ServerRunStage::SelectJavaRuntime)
+        }
+        ProcessStatus::Starting(StartingStatus::DownloadServerJar) => {
+            Some(ServerRunStage::DownloadServerJar)
+        }
+        ProcessStatus::Starting(StartingStatus::DownloadWorld) => {
+            Some(ServerRunStage::DownloadWorld)
+        }
+        ProcessStatus::Starting(StartingStatus::InstallAddons) => {
+            Some(ServerRunStage::InstallAddons)
+        }
+        ProcessStatus::Starting(StartingStatus::InitializeConfigFile) => {
+            Some(ServerRunStage::InitializeConfigFile)
+        }
+        ProcessStatus::Starting(StartingStatus::WaitingForServerReady) => {
+            Some(ServerRunStage::WaitingForServerReady)
+        }
+        ProcessStatus::Running => Some(ServerRunStage::Running),
+        ProcessStatus::Terminating => Some(ServerRunStage::Terminating),
+        ProcessStatus::Crashed(_) | ProcessStatus::Cancelled | ProcessStatus::Terminated => None,
+    }
+}
+
+impl From<&RunRecord> for ServerRun {
+    fn from(value: &RunRecord) -> Self {
+        let stages = value
+            .status
+            .iter()
+            .filter_map(|(status, info)| {
+                Some(ServerRunStageInfo {
+                    stage: run_stage(*status)?,
+                    start_time: info.start_time,
+                    end_time: info.end_time.unwrap_or(value.stop_time),
+                    error: info.error.clone(),
+                    exit_code: info.exit_code,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            start_time: value.start_time,
+            stop_time: value.stop_time,
+            result: match value.result {
+                RunResult::Stopped => ServerRunResult::Stopped,
+                RunResult::Crashed => ServerRunResult::Crashed,
+                RunResult::Failed => ServerRunResult::Failed,
+                RunResult::Cancelled => ServerRunResult::Cancelled,
+            },
+            final_stage: stages.last().map(|stage| stage.stage),
+            error: value.error.clone(),
+            exit_code: value.exit_code,
+            stages: ServerRunStages(stages),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ServerRunResult> for common::tonic_idl_gen::ServerRunResult {
+    fn from(value: ServerRunResult) -> Self {
+        match value {
+            ServerRunResult::Stopped => Self::RunStopped,
+            ServerRunResult::Crashed => Self::RunCrashed,
+            ServerRunResult::Failed => Self::RunFailed,
+            ServerRunResult::Cancelled => Self::RunCancelled,
+            ServerRunResult::Running => Self::RunRunning,
+            ServerRunResult::Interrupted => Self::RunInterrupted,
+        }
+    }
+}
+
+impl From<ServerRunStage> for RunningServerStage {
+    fn from(value: ServerRunStage) -> Self {
+        match value {
+            ServerRunStage::SelectJavaRuntime => RunningServerStage::SelectingJava,
+            ServerRunStage::DownloadServerJar => RunningServerStage::PullingServer,
+            ServerRunStage::DownloadWorld => RunningServerStage::PullingWorld,
+            ServerRunStage::InstallAddons => RunningServerStage::InstallingAddons,
+            ServerRunStage::InitializeConfigFile => RunningServerStage::InitializingFile,
+            ServerRunStage::WaitingForServerReady => RunningServerStage::Starting,
+            ServerRunStage::Running => RunningServerStage::Running,
+            ServerRunStage::Terminating => RunningServerStage::Stopping,
+        }
+    }
+}
+
+impl From<ServerRun> for common::tonic_idl_gen::ServerRun {
+    fn from(value: ServerRun) -> Self {
+        Self {
+            id: value.id,
+            server_config_id: value.server_config_id,
+            start_time: value.start_time.timestamp(),
+            stop_time: value.stop_time.timestamp(),
+            result: common::tonic_idl_gen::ServerRunResult::from(value.result) as i32,
+            final_stage: value
+                .final_stage
+                .map(|stage| RunningServerStage::from(stage) as i32),
+            error_message: value.error,
+            exit_code: value.exit_code,
+            stage_info: value
+                .stages
+                .0
+                .into_iter()
+                .map(|info| RunningServerStageInfo {
+                    stage: RunningServerStage::from(info.stage) as i32,
+                    enter_time: info.start_time.timestamp(),
+                    finish_time: Some(info.end_time.timestamp()),
+                    in_error: info.error.is_some(),
+                    error_message: info.error,
+                    exit_code: info.exit_code,
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::future::Future;
+
+use anyhow::Result;
+use md5::{Digest, Md5};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use tracing::warn;
+
+const MOJANG_API_BASE: &str = "https://api.mojang.com";
+
+// 根据玩家名获取UUID，玩家不存在时返回None
+pub trait UuidResolver {
+    fn resolve_uuid(&self, name: &str) -> impl Future<Output = Result<Option<String>>> + Send;
+}
+
+// 正版模式下服务器使用Mojang账号的UUID
+pub struct MojangResolver<'a> {
+    client: &'a Client,
+}
+
+impl<'a> MojangResolver<'a> {
+    pub fn new(client: &'a Client) -> Self {
+        Self { client }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MojangProfile {
+    id: String,
+}
+
+impl UuidResolver for MojangResolver<'_> {
+    async fn resolve_uuid(&self, name: &str) -> Result<Option<String>> {
+        let response = self
+            .client
+            .get(format!("{MOJANG_API_BASE}/users/profiles/minecraft/{name}"))
+            .send()
+            .await?;
+        if matches!(
+            response.status(),
+            StatusCode::NO_CONTENT | StatusCode::NOT_FOUND
+        ) {
+            return Ok(None);
+        }
+
+        let profile: MojangProfile = response.error_for_status()?.json().await?;
+        Ok(Some(hyphenate_uuid(&profile.id)))
+    }
+}
+
+// 离线模式下服务器根据玩家名计算UUID
+pub struct OfflineResolver;
+
+impl UuidResolver for OfflineResolver {
+    async fn resolve_uuid(&self, name: &str) -> Result<Option<String>> {
+        Ok(Some(offline_uuid(name)))
+    }
+}
+
+// 无法获取UUID时使用离线UUID，与离线模式服务器一致
+pub async fn resolve_player_uuid(resolver: &impl UuidResolver, name: &str) -> String {
+    match resolver.resolve_uuid(name).await {
+        Ok(Some(uuid)) => uuid,
+        Ok(None) => {
+            warn!("uuid of {name} not found, using offline uuid");
+            offline_uuid(name)
+        }
+        Err(e) => {
+            warn!("failed to resolve uuid of {name}, using offline uuid: {e:?}");
+            offline_uuid(name)
+        }
+    }
+}
+
+// 与服务端一致：UUID v3，命名空间为"OfflinePlayer:"
+fn offline_uuid(name: &str) -> String {
+    let mut hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{name}")).into();
+    hash[6] = hash[6] & 0x0f | 0x30;
+    hash[8] = hash[8] & 0x3f | 0x80;
+
+    hyphenate_uuid(&hash.iter().map(|n| format!("{n:02x}")).collect::<String>())
+}
+
+fn hyphenate_uuid(uuid: &str) -> String {
+    if uuid.len() != 32 {
+        return uuid.to_owned();
+    }
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &uuid[0..8],
+        &uuid[8..12],
+        &uuid[12..16],
+        &uuid[16..20],
+        &uuid[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuid_known_value() {
+        assert_eq!(
+            offline_uuid("Notch"),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+    }
+
+    #[test]
+    fn hyphenate_mojang_uuid() {
+        assert_eq!(
+            hyphenate_uuid("069a79f444e94726a5befca90e38aaf5"),
+            "069a79f4-44e9-4726-a5be-fca90e38aaf5"
+        );
+        assert_eq!(hyphenate_uuid("not-a-uuid"), "not-a-uuid");
+    }
+
+    #[tokio::test]
+    async fn offline_resolver_never_fails() {
+        assert_eq!(
+            resolve_player_uuid(&OfflineResolver, "Notch").await,
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+    }
+}
@@ -5,7 +5,7 @@ use reqwest::Client;
 use serde::Deserialize;
 use server_common::db::context::{Context, ContextRef};
 use sqlx::Database;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::dao::version::{ListVersionParameters, Version, VersionRepository, VersionType};
 
@@ -41,8 +41,16 @@ struct LatestVersion {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct VersionDetail {
     downloads: VersionDownloads,
+    java_version: Option<JavaVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JavaVersion {
+    major_version: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -53,6 +61,8 @@ struct VersionDownloads {
 #[derive(Debug, Deserialize)]
 struct VersionDownloadDetail {
     url: String,
+    sha1: String,
+    size: u64,
 }
 
 async fn get_remote_version_manifest(client: &Client) -> Result<RemoteVersionManifest> {
@@ -74,6 +84,38 @@ where
 {
     let remote_versions = get_remote_version_manifest(client).await?;
 
+    // 补全旧数据中缺失的java版本和服务端校验信息，详情中也没有的字段保持未知，不再重复获取
+    for mut version in db.list_version_incomplete().await? {
+        let Some(remote) = remote_versions
+            .versions
+            .iter()
+            .find(|remote| remote.id == version.mc_id)
+        else {
+            continue;
+        };
+
+        // 单个版本获取失败时不影响其他版本，下次同步时重试
+        let version_detail = match get_remote_version_detail(client, &remote.url).await {
+            Ok(version_detail) => version_detail,
+            Err(e) => {
+                warn!("failed to fetch detail of version {}: {e:?}", version.mc_id);
+                continue;
+            }
+        };
+        if let Some(java_version) = version_detail.java_version {
+            version.java_major_version = java_version.major_version;
+        }
+        if let Some(server) = version_detail.downloads.server {
+            version.server_sha1 = server.sha1;
+            version.server_size = server.size;
+        }
+        info!(
+            "version {} requires java {}, server sha1: {}",
+            version.mc_id, version.java_major_version, version.server_sha1
+        );
+        db.update_version_detail(&version).await?;
+    }
+
     // 检查最新版本（含快照）是否在数据库中，如果存在，则无需更新
     let latest_release = remote_versions.latest.release;
     let latest_snapshot = remote_versions.latest.snapshot;
@@ -124,6 +166,13 @@ where
             mc_id: version.id,
             r#type: version.r#type.into(),
             server_url: server.url,
+            server_sha1: server.sha1,
+            server_size: server.size,
+            java_major_version: version_detail
+                .java_version
+                .map(|java_version| java_version.major_version)
+                .unwrap_or_default(),
+            detail_checked: true,
             release_time: version.release_time,
             ..Default::default()
         })
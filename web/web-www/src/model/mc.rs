@@ -14,12 +14,75 @@ pub struct ListServerConfigResponse {
     pub configs: Vec<ServerConfig>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ServerConfig {
     pub id: u64,
     pub name: String,
     pub version: String,
+    pub flavor: ServerFlavor,
     pub motd: String,
+    pub restart_policy: RestartPolicy,
+    pub restart_max_retries: u32,
+    pub properties: ServerProperties,
+    pub jvm_options: JvmOptions,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerFlavor {
+    Vanilla,
+    Paper,
+    Fabric,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    Never,
+    OnFailure,
+    Always,
+}
+
+// 未设置的字段使用服务器默认值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerProperties {
+    pub gamemode: Option<GameMode>,
+    pub difficulty: Option<Difficulty>,
+    pub max_players: Option<u32>,
+    pub pvp: Option<bool>,
+    pub level_seed: Option<String>,
+    pub white_list: Option<bool>,
+    pub online_mode: Option<bool>,
+    pub view_distance: Option<u32>,
+    pub enable_command_block: Option<bool>,
+    pub require_resource_pack: Option<bool>,
+}
+
+// 内存大小单位为MB
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JvmOptions {
+    pub min_memory_mb: Option<u32>,
+    pub max_memory_mb: Option<u32>,
+    pub extra_args: Vec<String>,
+    pub aikar_flags: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
 }
 
 #[derive(Debug, Serialize)]
@@ -47,9 +110,30 @@ pub struct McVersion {
 pub struct CreateServerConfigRequest {
     pub name: String,
     pub version: String,
+    pub flavor: ServerFlavor,
     pub world_uri: Option<String>,
     pub resource_uri: Option<String>,
     pub motd: String,
+    pub restart_policy: RestartPolicy,
+    pub restart_max_retries: u32,
+    pub properties: ServerProperties,
+    pub jvm_options: JvmOptions,
+}
+
+// 未设置的字段保持不变
+#[derive(Debug, Serialize)]
+pub struct UpdateServerConfigRequest {
+    pub id: u64,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub flavor: Option<ServerFlavor>,
+    pub world_uri: Option<String>,
+    pub resource_uri: Option<String>,
+    pub motd: Option<String>,
+    pub restart_policy: Option<RestartPolicy>,
+    pub restart_max_retries: Option<u32>,
+    pub properties: Option<ServerProperties>,
+    pub jvm_options: Option<JvmOptions>,
 }
 
 #[derive(Debug, Serialize)]
@@ -57,23 +141,56 @@ pub struct StartServerConfigRequest {
     pub id: u64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct StopServerConfigRequest {
+    pub id: u64,
+}
+
 #[derive(Debug, Deserialize)]
-pub struct GetCurrentServerConfigResponse {
-    #[allow(unused)]
-    pub running_config: Option<ServerConfig>,
+pub struct ListRunningServersResponse {
+    pub servers: Vec<RunningServer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunningServer {
+    pub config: ServerConfig,
+    pub port: u32,
     pub status: HashMap<RunningServerStage, RunningServerStageInfo>,
+    pub crashes: Vec<RunningServerStageInfo>,
+    pub list_status: Option<ServerListStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerListStatus {
+    pub version: String,
+    pub online_players: u32,
+    pub max_players: u32,
+    pub players: Vec<ServerPlayerSample>,
+    pub latency_ms: u32,
+    #[allow(unused)]
+    pub update_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerPlayerSample {
+    pub name: String,
+    #[allow(unused)]
+    pub id: String,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
 pub enum RunningServerStage {
     Init,
+    SelectingJava,
     PullingServer,
     PullingWorld,
+    InstallingAddons,
     InitializingFile,
     Starting,
     Running,
     Stopping,
+    Cancelled,
     Stopped,
 }
 
@@ -82,6 +199,28 @@ pub struct RunningServerStageInfo {
     pub enter_time: i64,
     pub finish_time: Option<i64>,
     pub in_error: bool,
-    #[allow(unused)]
     pub error_message: Option<String>,
+    pub exit_code: Option<i32>,
+    pub bytes_done: Option<u64>,
+    pub bytes_total: Option<u64>,
+    pub entries_extracted: Option<u64>,
+    pub entries_total: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamServerConsoleRequest {
+    pub id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerConsoleLine {
+    #[allow(unused)]
+    pub time: i64,
+    pub line: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SendServerCommandRequest {
+    pub id: u64,
+    pub command: String,
 }
@@ -1,20 +1,24 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
 
 use anyhow::{anyhow, Error, Result};
-use gloo_net::http::Headers;
-use gloo_timers::callback::Interval;
+use futures::StreamExt;
+use gloo_net::{eventsource::futures::EventSource, http::Headers};
+use gloo_timers::callback::{Interval, Timeout};
 use log::{error, info};
 use wasm_bindgen::JsCast;
-use web_sys::{File, HtmlInputElement, HtmlTextAreaElement};
+use web_sys::{Element, File, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
 use yew::{html::Scope, prelude::*};
 
 use crate::{
     component::*,
     model::{
         mc::{
-            CreateServerConfigRequest, GetCurrentServerConfigResponse, ListMcVersionRequest,
-            ListMcVersionResponse, ListServerConfigRequest, ListServerConfigResponse, McVersion,
-            RunningServerStage, RunningServerStageInfo, ServerConfig, StartServerConfigRequest,
+            CreateServerConfigRequest, Difficulty, GameMode, JvmOptions, ListMcVersionRequest,
+            ListMcVersionResponse, ListRunningServersResponse, ListServerConfigRequest,
+            ListServerConfigResponse, McVersion, RestartPolicy, RunningServer, RunningServerStage,
+            RunningServerStageInfo, SendServerCommandRequest, ServerConfig, ServerConsoleLine,
+            ServerFlavor, ServerProperties, StartServerConfigRequest, StopServerConfigRequest,
+            StreamServerConsoleRequest, UpdateServerConfigRequest,
         },
         oss::GetUploadSignatureResponse,
         EncryptRequest,
@@ -25,24 +29,46 @@ use crate::{
 pub struct McServerManagePage {
     // interval
     // 保留此信息，在drop后interval会停止
-    _fetch_current_server_config_interval: Interval,
+    _fetch_running_servers_interval: Interval,
+    // 保留此信息，在drop后连接会关闭
+    console_event_source: Option<EventSource>,
 
     // node
+    console_output: NodeRef,
+    console_command: NodeRef,
     create_server_config_dialog: NodeRef,
     create_server_config_modal: Rc<RefCell<Option<Modal>>>,
     create_server_config_name: NodeRef,
     create_server_config_version: NodeRef,
+    create_server_config_flavor: NodeRef,
     create_server_config_motd: NodeRef,
+    create_server_config_restart_policy: NodeRef,
+    create_server_config_restart_max_retries: NodeRef,
+    create_server_config_properties: ServerPropertiesForm,
+    create_server_config_jvm_options: JvmOptionsForm,
+    edit_server_config_dialog: NodeRef,
+    edit_server_config_modal: Rc<RefCell<Option<Modal>>>,
+    edit_server_config_name: NodeRef,
+    edit_server_config_version: NodeRef,
+    edit_server_config_flavor: NodeRef,
+    edit_server_config_motd: NodeRef,
+    edit_server_config_restart_policy: NodeRef,
+    edit_server_config_restart_max_retries: NodeRef,
+    edit_server_config_properties: ServerPropertiesForm,
+    edit_server_config_jvm_options: JvmOptionsForm,
 
     // data
-    current_server_config: HashMap<RunningServerStage, RunningServerStageInfo>,
-    current_server_config_status: RunningServerStage,
-    current_server_config_error: bool,
+    running_servers: Vec<RunningServer>,
+    console_server_id: Option<u64>,
     server_config_total: Option<i64>,
     server_configs: Option<Vec<ServerConfig>>,
     server_config_page: u64,
     mc_versions: Vec<String>,
     with_snapshot_version: bool,
+    console_lines: VecDeque<String>,
+    editing_server_config: Option<ServerConfig>,
+    // 每次打开对话框时递增，使表单重新渲染为初始值
+    dialog_serial: u64,
 
     // status
     upload_world: UploadUriStatus,
@@ -80,8 +106,8 @@ enum SubmitStatus {
 }
 
 pub enum McServerManagePageMsg {
-    LoadCurrentServerConfig {
-        current_server_config: HashMap<RunningServerStage, RunningServerStageInfo>,
+    LoadRunningServers {
+        servers: Vec<RunningServer>,
     },
     LoadServerConfig {
         total: i64,
@@ -114,13 +140,36 @@ pub enum McServerManagePageMsg {
     ErrorUploadResourceFile {
         err: String,
     },
+    ShowCreateServerConfig,
     SubmitCreateServerConfig,
     FinishCreateServerConfig,
     ErrorCreateServerConfig {
         err: String,
     },
+    ShowEditServerConfig {
+        id: u64,
+    },
+    SubmitUpdateServerConfig,
+    FinishUpdateServerConfig,
+    ErrorUpdateServerConfig {
+        err: String,
+    },
+    SelectConsoleServer {
+        id: u64,
+    },
+    ConnectConsole,
+    ReceiveConsoleLine {
+        id: u64,
+        line: ServerConsoleLine,
+    },
+    DisconnectConsole {
+        id: u64,
+    },
+    SubmitServerCommand,
 }
 
+const MAX_CONSOLE_LINES: usize = 500;
+
 impl McServerManagePage {
     fn load_server_configs(page: u64, link: Scope<Self>) {
         wasm_bindgen_futures::spawn_local(async move {
@@ -258,32 +307,90 @@ impl McServerManagePage {
         Ok(())
     }
 
-    fn load_current_server_config(link: Scope<Self>) {
+    fn update_server_config(req: UpdateServerConfigRequest, link: Scope<Self>) {
         wasm_bindgen_futures::spawn_local(async move {
-            match Self::load_current_server_config_imp().await {
-                Ok(current_server_config) => {
-                    link.send_message(McServerManagePageMsg::LoadCurrentServerConfig {
-                        current_server_config,
-                    });
+            match Self::update_server_config_imp(req).await {
+                Ok(()) => link.send_message(McServerManagePageMsg::FinishUpdateServerConfig),
+                Err(err) => link.send_message(McServerManagePageMsg::ErrorUpdateServerConfig {
+                    err: err.to_string(),
+                }),
+            }
+        });
+    }
+
+    async fn update_server_config_imp(req: UpdateServerConfigRequest) -> Result<()> {
+        let encrypt = EncryptRequest::encrypt_payload(&serde_json::to_vec(&req)?)?;
+        let data = rmp_serde::to_vec(&encrypt)?;
+
+        let response = gloo_net::http::Request::post("/api/mc/server_config/update")
+            .body(data)?
+            .send()
+            .await?;
+
+        // 运行中修改版本、存档等会被拒绝
+        if !response.ok() {
+            return Err(anyhow!("update failed with status: {}", response.status()));
+        }
+
+        Ok(())
+    }
+
+    fn select_flavor(select: &NodeRef) -> ServerFlavor {
+        match select
+            .cast::<HtmlSelectElement>()
+            .expect("flavor should be a select element")
+            .value()
+            .as_str()
+        {
+            "paper" => ServerFlavor::Paper,
+            "fabric" => ServerFlavor::Fabric,
+            _ => ServerFlavor::Vanilla,
+        }
+    }
+
+    // 两个对话框共用上传和提交状态，打开时重置
+    fn show_dialog(&mut self, dialog: &NodeRef, modal: &Rc<RefCell<Option<Modal>>>) {
+        self.upload_world = UploadUriStatus::default();
+        self.upload_resource = UploadUriStatus::default();
+        self.submit_status = SubmitStatus::default();
+        self.dialog_serial += 1;
+
+        if modal.borrow().is_none() {
+            let element = dialog
+                .cast()
+                .expect("Cannot cast dialog node_ref to Element");
+            *modal.borrow_mut() = Some(Modal::new_with_element(&element))
+        }
+        modal
+            .borrow()
+            .as_ref()
+            .expect("modal should be initialized before")
+            .show();
+    }
+
+    fn load_running_servers(link: Scope<Self>) {
+        wasm_bindgen_futures::spawn_local(async move {
+            match Self::load_running_servers_imp().await {
+                Ok(servers) => {
+                    link.send_message(McServerManagePageMsg::LoadRunningServers { servers });
                 }
                 Err(err) => error!("{err}"),
             };
         });
     }
 
-    async fn load_current_server_config_imp(
-    ) -> Result<HashMap<RunningServerStage, RunningServerStageInfo>> {
+    async fn load_running_servers_imp() -> Result<Vec<RunningServer>> {
         let encrypt = EncryptRequest::encrypt_payload(&serde_json::to_vec(&())?)?;
 
-        let response: GetCurrentServerConfigResponse =
-            gloo_net::http::Request::get("/api/mc/server_config/process/info")
+        let response: ListRunningServersResponse =
+            gloo_net::http::Request::get("/api/mc/server_config/process/list")
                 .query(encrypt.to_query_params())
                 .send()
                 .await?
                 .json()
                 .await?;
 
-        Ok(response.status)
+        Ok(response.servers)
     }
 
     fn start_server_config(id: u64) {
@@ -310,17 +417,18 @@ impl McServerManagePage {
         Ok(())
     }
 
-    fn stop_server_config() {
+    fn stop_server_config(id: u64) {
         wasm_bindgen_futures::spawn_local(async move {
-            match Self::stop_server_config_imp().await {
+            match Self::stop_server_config_imp(id).await {
                 Ok(_) => (),
                 Err(e) => error!("{e}"),
             };
         });
     }
 
-    async fn stop_server_config_imp() -> Result<()> {
-        let encrypt = EncryptRequest::encrypt_payload(&serde_json::to_vec(&())?)?;
+    async fn stop_server_config_imp(id: u64) -> Result<()> {
+        let encrypt =
+            EncryptRequest::encrypt_payload(&serde_json::to_vec(&StopServerConfigRequest { id })?)?;
         let data = rmp_serde::to_vec(&encrypt)?;
 
         gloo_net::http::Request::post("/api/mc/server_config/process/stop")
@@ -330,6 +438,385 @@ impl McServerManagePage {
 
         Ok(())
     }
+
+    fn send_server_command(id: u64, command: String) {
+        wasm_bindgen_futures::spawn_local(async move {
+            match Self::send_server_command_imp(id, command).await {
+                Ok(_) => (),
+                Err(e) => error!("{e}"),
+            };
+        });
+    }
+
+    async fn send_server_command_imp(id: u64, command: String) -> Result<()> {
+        let encrypt =
+            EncryptRequest::encrypt_payload(&serde_json::to_vec(&SendServerCommandRequest {
+                id,
+                command,
+            })?)?;
+        let data = rmp_serde::to_vec(&encrypt)?;
+
+        gloo_net::http::Request::post("/api/mc/server_config/process/command")
+            .body(data)?
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
+    fn connect_console(id: u64, link: Scope<Self>) -> Result<EventSource> {
+        let encrypt =
+            EncryptRequest::encrypt_payload(&serde_json::to_vec(&StreamServerConsoleRequest {
+                id,
+            })?)?;
+        let query = encrypt
+            .to_query_params()
+            .map(|(key, value)| format!("{key}={value}"))
+            .join("&");
+
+        let mut event_source =
+            EventSource::new(&format!("/api/mc/server_config/process/console?{query}"))?;
+        let mut subscription = event_source.subscribe("message")?;
+
+        wasm_bindgen_futures::spawn_local(async move {
+            // 出错时浏览器会使用原有url重连，但加密请求已过期，因此由页面重新建立连接
+            while let Some(Ok((_, event))) = subscription.next().await {
+                let Some(data) = event.data().as_string() else {
+                    continue;
+                };
+
+                match serde_json::from_str(&data) {
+                    Ok(line) => {
+                        link.send_message(McServerManagePageMsg::ReceiveConsoleLine { id, line })
+                    }
+                    Err(err) => error!("{err}"),
+                }
+            }
+
+            link.send_message(McServerManagePageMsg::DisconnectConsole { id });
+        });
+
+        Ok(event_source)
+    }
+}
+
+impl McServerManagePage {
+    fn view_running_server(&self, server: &RunningServer) -> Html {
+        let status = server
+            .status
+            .keys()
+            .max()
+            .copied()
+            .unwrap_or(RunningServerStage::Init);
+        let in_error = server.status.values().any(|info| info.in_error);
+        // 崩溃记录未结束说明正在等待重启
+        let waiting_restart = server.status.is_empty()
+            && server
+                .crashes
+                .last()
+                .is_some_and(|crash| crash.finish_time.is_none());
+
+        let on_stop_config = {
+            let id = server.config.id;
+            Callback::from(move |_e: MouseEvent| {
+                Self::stop_server_config(id);
+            })
+        };
+
+        html! {
+            <div class="card" style="margin-bottom: 12px;">
+                <div class="card-header">
+                    <span class="card-text">
+                        {format!("{}（端口 {}）：", server.config.name, server.port)}
+                        {
+                            if waiting_restart {
+                                html! {
+                                    <span class="badge bg-warning text-dark">{"等待重启"}</span>
+                                }
+                            } else if in_error {
+                                html! {
+                                    <span class="badge bg-danger">{"已停止"}</span>
+                                }
+                            } else {
+                                match status {
+                                    RunningServerStage::Init | RunningServerStage::Stopped => html! {
+                                        <span class="badge bg-info text-dark">{"已停止"}</span>
+                                    },
+                                    RunningServerStage::SelectingJava | RunningServerStage::PullingServer | RunningServerStage::PullingWorld | RunningServerStage::InstallingAddons | RunningServerStage::InitializingFile | RunningServerStage::Starting => html! {
+                                        <span class="badge bg-primary">{"启动中"}</span>
+                                    },
+                                    RunningServerStage::Running => html! {
+                                        <span class="badge bg-success">{"运行中"}</span>
+                                    },
+                                    RunningServerStage::Stopping => html! {
+                                        <span class="badge bg-info text-dark">{"停止中"}</span>
+                                    },
+                                    RunningServerStage::Cancelled => html! {
+                                        <span class="badge bg-info text-dark">{"已取消启动"}</span>
+                                    }
+                                }
+                            }
+                        }
+                    </span>
+                    {
+                        if let Some(crash) = server.crashes.last() {
+                            let exit_code = crash
+                                .exit_code
+                                .map(|code| code.to_string())
+                                .unwrap_or("-".to_string());
+                            html! {
+                                <span class="badge bg-warning text-dark" style="margin-left: 8px;" title={crash.error_message.clone()}>
+                                    {format!("已崩溃 {} 次，最近退出码：{}", server.crashes.len(), exit_code)}
+                                </span>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        if let Some(list_status) = &server.list_status {
+                            let players = list_status
+                                .players
+                                .iter()
+                                .map(|player| player.name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            html! {
+                                <span class="badge bg-secondary" style="margin-left: 8px;" title={players}>
+                                    {format!("{} 在线 {}/{}，延迟 {}ms", list_status.version, list_status.online_players, list_status.max_players, list_status.latency_ms)}
+                                </span>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    <button type="button" class="btn btn-sm btn-outline-danger float-end" onclick={on_stop_config}>{"停止服务器"}</button>
+                </div>
+                <div class="card-body" style="overflow-x: auto; padding: 2rem;">
+                    <div class="row g-0" style="width: 2700px;">
+                        {
+                            [
+                                (RunningServerStage::SelectingJava, "选择Java运行时", true),
+                                (RunningServerStage::PullingServer, "下载服务端程序", true),
+                                (RunningServerStage::PullingWorld, "下载存档", true),
+                                (RunningServerStage::InstallingAddons, "安装模组/插件", true),
+                                (RunningServerStage::InitializingFile, "生成配置文件", true),
+                                (RunningServerStage::Starting, "启动服务器", true),
+                                (RunningServerStage::Running, "等待游戏结束", true),
+                                (RunningServerStage::Stopping, "停止服务器", false)
+                            ].into_iter().map(|(stage, title, has_next)| {
+                                let stage = server.status.get(&stage);
+                                let icon = if let Some(stage) = stage {
+                                    if stage.in_error {
+                                        ProgressIcon::Error
+                                    } else if stage.finish_time.is_some() {
+                                        ProgressIcon::Pass
+                                    } else {
+                                        ProgressIcon::Running
+                                    }
+                                } else {
+                                    ProgressIcon::Waiting
+                                };
+
+                                let start_time = stage.as_ref().map(|stage| stage.enter_time);
+                                let end_time = stage.as_ref().and_then(|stage| stage.finish_time);
+                                let progress = stage.and_then(stage_progress);
+                                let error = stage.and_then(|stage| stage.error_message.clone()).map(AttrValue::from);
+
+                                html! {
+                                    <ProgressNode title={title} has_next={has_next} icon={icon} start_time={start_time} end_time={end_time} progress={progress} error={error} />
+                                }
+                            }).collect::<Html>()
+                        }
+                    </div>
+                </div>
+            </div>
+        }
+    }
+
+    fn view_edit_server_config_form(
+        &self,
+        config: &ServerConfig,
+        world_file_select: Callback<Event>,
+        resource_file_select: Callback<Event>,
+    ) -> Html {
+        // 版本列表中可能没有当前版本（如快照版），需要单独加入
+        let has_current_version = self.mc_versions.contains(&config.version);
+
+        html! {
+            <div class="modal-body" key={format!("edit-{}-{}", config.id, self.dialog_serial)}>
+                <div class="row mb-3">
+                    {
+                        if let SubmitStatus::Error{ err } = &self.submit_status {
+                            html! {
+                                <div class="alert alert-danger" role="alert">
+                                    {"提交失败："} {err.to_string()}
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_name" class="col-sm-4 col-form-label">
+                        {"服务器名"}
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="text" class="form-control" id="edit_name" maxlength={100} required={true}
+                            value={config.name.clone()} ref={self.edit_server_config_name.clone()} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_version" class="col-sm-4 col-form-label">
+                        {"服务器版本"}
+                    </label>
+                    <div class="col-sm-8">
+                        <select class="form-control form-select" id="edit_version" required={true} ref={self.edit_server_config_version.clone()}>
+                            {
+                                if has_current_version {
+                                    html! {}
+                                } else {
+                                    html! {
+                                        <option value={config.version.clone()} selected={true}>{config.version.clone()}</option>
+                                    }
+                                }
+                            }
+                            {
+                                self.mc_versions.iter().map(|version| {
+                                    html! {
+                                        <option value={version.clone()} selected={*version == config.version}>{version.clone()}</option>
+                                    }
+                                }).collect::<Html>()
+                            }
+                        </select>
+                        <p class="form-text">{"服务器运行中时不能修改版本、服务端类型、存档和资源包"}</p>
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_flavor" class="col-sm-4 col-form-label">
+                        {"服务端类型"}
+                    </label>
+                    <div class="col-sm-8">
+                        <select class="form-control form-select" id="edit_flavor" ref={self.edit_server_config_flavor.clone()}>
+                            <option value="vanilla" selected={config.flavor == ServerFlavor::Vanilla}>{"原版"}</option>
+                            <option value="paper" selected={config.flavor == ServerFlavor::Paper}>{"Paper"}</option>
+                            <option value="fabric" selected={config.flavor == ServerFlavor::Fabric}>{"Fabric"}</option>
+                        </select>
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_world" class="col-sm-4 col-form-label">
+                        {"替换存档"}
+                        {
+                            if matches!(self.upload_world, UploadUriStatus::Uploading) {
+                                html! {
+                                    <div class="spinner-border spinner-border-sm" role="status">
+                                        <span class="visually-hidden">{"上传中"}</span>
+                                    </div>
+                                }
+                            } else {
+                                html!{}
+                            }
+                        }
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="file" class="form-control" id="edit_world" accept=".zip"
+                            onchange={world_file_select} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    {
+                        if let UploadUriStatus::Error { err } = &self.upload_world {
+                            html! {
+                                <p style="color: red">
+                                    {"上传文件失败，请重新选择文件后重试："} {err.clone()}
+                                </p>
+                            }
+                        } else {
+                            html!{}
+                        }
+                    }
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_resource" class="col-sm-4 col-form-label">
+                        {"替换资源包"}
+                        {
+                            if matches!(self.upload_resource, UploadUriStatus::Uploading) {
+                                html! {
+                                    <div class="spinner-border spinner-border-sm" role="status">
+                                        <span class="visually-hidden">{"上传中"}</span>
+                                    </div>
+                                }
+                            } else {
+                                html!{}
+                            }
+                        }
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="file" class="form-control" id="edit_resource" accept=".zip"
+                            onchange={resource_file_select} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    {
+                        if let UploadUriStatus::Error { err } = &self.upload_resource {
+                            html! {
+                                <p style="color: red">
+                                    {"上传文件失败，请重新选择文件后重试："} {err.clone()}
+                                </p>
+                            }
+                        } else {
+                            html!{}
+                        }
+                    }
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_motd" class="col-sm-4 col-form-label">
+                        {"MOTD"}
+                    </label>
+                    <div class="col-sm-8">
+                        <textarea class="form-control" id="edit_motd" value={config.motd.clone()} ref={self.edit_server_config_motd.clone()} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_restart_policy" class="col-sm-4 col-form-label">
+                        {"崩溃重启"}
+                    </label>
+                    <div class="col-sm-8">
+                        <select class="form-control form-select" id="edit_restart_policy" ref={self.edit_server_config_restart_policy.clone()}>
+                            <option value="never" selected={config.restart_policy == RestartPolicy::Never}>{"不重启"}</option>
+                            <option value="on_failure" selected={config.restart_policy == RestartPolicy::OnFailure}>{"异常退出时重启"}</option>
+                            <option value="always" selected={config.restart_policy == RestartPolicy::Always}>{"总是重启"}</option>
+                        </select>
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for="edit_restart_max_retries" class="col-sm-4 col-form-label">
+                        {"最大重试次数"}
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="number" class="form-control" id="edit_restart_max_retries" min="0" max="100"
+                            value={config.restart_max_retries.to_string()} ref={self.edit_server_config_restart_max_retries.clone()} />
+                    </div>
+                </div>
+
+                { self.edit_server_config_properties.view("edit", &config.properties) }
+
+                { self.edit_server_config_jvm_options.view("edit", &config.jvm_options) }
+            </div>
+        }
+    }
 }
 
 impl Component for McServerManagePage {
@@ -339,25 +826,45 @@ impl Component for McServerManagePage {
     fn create(ctx: &Context<Self>) -> Self {
         Self::load_server_configs(1, ctx.link().clone());
         Self::load_mc_versions(false, ctx.link().clone());
-        Self::load_current_server_config(ctx.link().clone());
+        Self::load_running_servers(ctx.link().clone());
 
-        let fetch_current_server_config_interval = {
+        let fetch_running_servers_interval = {
             let link = ctx.link().clone();
             Interval::new(5000, move || {
-                Self::load_current_server_config(link.clone());
+                Self::load_running_servers(link.clone());
             })
         };
 
         Self {
-            _fetch_current_server_config_interval: fetch_current_server_config_interval,
-            current_server_config: HashMap::new(),
-            current_server_config_status: RunningServerStage::Init,
-            current_server_config_error: false,
+            _fetch_running_servers_interval: fetch_running_servers_interval,
+            console_event_source: None,
+            console_output: NodeRef::default(),
+            console_command: NodeRef::default(),
+            console_lines: VecDeque::new(),
+            running_servers: Vec::new(),
+            console_server_id: None,
             create_server_config_dialog: NodeRef::default(),
             create_server_config_modal: Rc::new(RefCell::new(None)),
             create_server_config_name: NodeRef::default(),
             create_server_config_version: NodeRef::default(),
+            create_server_config_flavor: NodeRef::default(),
             create_server_config_motd: NodeRef::default(),
+            create_server_config_restart_policy: NodeRef::default(),
+            create_server_config_restart_max_retries: NodeRef::default(),
+            create_server_config_properties: ServerPropertiesForm::default(),
+            create_server_config_jvm_options: JvmOptionsForm::default(),
+            edit_server_config_dialog: NodeRef::default(),
+            edit_server_config_modal: Rc::new(RefCell::new(None)),
+            edit_server_config_name: NodeRef::default(),
+            edit_server_config_version: NodeRef::default(),
+            edit_server_config_flavor: NodeRef::default(),
+            edit_server_config_motd: NodeRef::default(),
+            edit_server_config_restart_policy: NodeRef::default(),
+            edit_server_config_restart_max_retries: NodeRef::default(),
+            edit_server_config_properties: ServerPropertiesForm::default(),
+            edit_server_config_jvm_options: JvmOptionsForm::default(),
+            editing_server_config: None,
+            dialog_serial: 0,
             server_config_page: 1,
             server_configs: None,
             server_config_total: None,
@@ -371,20 +878,18 @@ impl Component for McServerManagePage {
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            McServerManagePageMsg::LoadCurrentServerConfig {
-                current_server_config,
-            } => {
-                self.current_server_config = current_server_config;
-                self.current_server_config_status = self
-                    .current_server_config
-                    .keys()
-                    .max()
-                    .copied()
-                    .unwrap_or(RunningServerStage::Init);
-                self.current_server_config_error = self
-                    .current_server_config
-                    .values()
-                    .any(|info| info.in_error);
+            McServerManagePageMsg::LoadRunningServers { servers } => {
+                self.running_servers = servers;
+
+                // 未选择控制台时默认连接第一个服务器
+                if self.console_server_id.is_none() {
+                    if let Some(server) = self.running_servers.first() {
+                        ctx.link()
+                            .send_message(McServerManagePageMsg::SelectConsoleServer {
+                                id: server.config.id,
+                            });
+                    }
+                }
                 return true;
             }
             McServerManagePageMsg::LoadServerConfig {
@@ -450,6 +955,12 @@ impl Component for McServerManagePage {
                 self.upload_resource = UploadUriStatus::Error { err };
                 return true;
             }
+            McServerManagePageMsg::ShowCreateServerConfig => {
+                let dialog = self.create_server_config_dialog.clone();
+                let modal = self.create_server_config_modal.clone();
+                self.show_dialog(&dialog, &modal);
+                return true;
+            }
             McServerManagePageMsg::SubmitCreateServerConfig => {
                 self.submit_status = SubmitStatus::Submitting;
 
@@ -465,6 +976,8 @@ impl Component for McServerManagePage {
                     .expect("version should be a input element")
                     .value();
 
+                let flavor = Self::select_flavor(&self.create_server_config_flavor);
+
                 let world_uri = if let UploadUriStatus::Finish { uri } = &self.upload_world {
                     Some(uri.clone())
                 } else {
@@ -483,15 +996,43 @@ impl Component for McServerManagePage {
                     .expect("motd should be a text area element")
                     .value();
 
-                info!("name: {name}, version: {version}, world_uri: {world_uri:?}, resource_uri: {resource_uri:?}, motd: {motd}");
+                let restart_policy = match self
+                    .create_server_config_restart_policy
+                    .cast::<HtmlSelectElement>()
+                    .expect("restart policy should be a select element")
+                    .value()
+                    .as_str()
+                {
+                    "on_failure" => RestartPolicy::OnFailure,
+                    "always" => RestartPolicy::Always,
+                    _ => RestartPolicy::Never,
+                };
+
+                let restart_max_retries = self
+                    .create_server_config_restart_max_retries
+                    .cast::<HtmlInputElement>()
+                    .expect("restart max retries should be a input element")
+                    .value()
+                    .parse()
+                    .unwrap_or(0);
+
+                let properties = self.create_server_config_properties.value();
+                let jvm_options = self.create_server_config_jvm_options.value();
+
+                info!("name: {name}, version: {version}, flavor: {flavor:?}, world_uri: {world_uri:?}, resource_uri: {resource_uri:?}, motd: {motd}, restart_policy: {restart_policy:?}, properties: {properties:?}, jvm_options: {jvm_options:?}");
 
                 Self::create_server_config(
                     CreateServerConfigRequest {
                         name,
                         version,
+                        flavor,
                         world_uri,
                         resource_uri,
                         motd,
+                        restart_policy,
+                        restart_max_retries,
+                        properties,
+                        jvm_options,
                     },
                     ctx.link().clone(),
                 );
@@ -512,25 +1053,216 @@ impl Component for McServerManagePage {
                 self.submit_status = SubmitStatus::Error { err };
                 return true;
             }
+            McServerManagePageMsg::ShowEditServerConfig { id } => {
+                self.editing_server_config = self
+                    .server_configs
+                    .iter()
+                    .flatten()
+                    .find(|config| config.id == id)
+                    .cloned();
+                if self.editing_server_config.is_none() {
+                    return false;
+                }
+
+                let dialog = self.edit_server_config_dialog.clone();
+                let modal = self.edit_server_config_modal.clone();
+                self.show_dialog(&dialog, &modal);
+                return true;
+            }
+            McServerManagePageMsg::SubmitUpdateServerConfig => {
+                let Some(config) = self.editing_server_config.clone() else {
+                    return false;
+                };
+                self.submit_status = SubmitStatus::Submitting;
+
+                // 只提交有改动的字段
+                let name = self
+                    .edit_server_config_name
+                    .cast::<HtmlInputElement>()
+                    .expect("name should be a input element")
+                    .value();
+
+                let version = self
+                    .edit_server_config_version
+                    .cast::<HtmlSelectElement>()
+                    .expect("version should be a select element")
+                    .value();
+
+                let flavor = Self::select_flavor(&self.edit_server_config_flavor);
+
+                let motd = self
+                    .edit_server_config_motd
+                    .cast::<HtmlTextAreaElement>()
+                    .expect("motd should be a text area element")
+                    .value();
+
+                let restart_policy = match self
+                    .edit_server_config_restart_policy
+                    .cast::<HtmlSelectElement>()
+                    .expect("restart policy should be a select element")
+                    .value()
+                    .as_str()
+                {
+                    "on_failure" => RestartPolicy::OnFailure,
+                    "always" => RestartPolicy::Always,
+                    _ => RestartPolicy::Never,
+                };
+
+                let restart_max_retries = self
+                    .edit_server_config_restart_max_retries
+                    .cast::<HtmlInputElement>()
+                    .expect("restart max retries should be a input element")
+                    .value()
+                    .parse()
+                    .unwrap_or(0);
+
+                let properties = self.edit_server_config_properties.value();
+                let jvm_options = self.edit_server_config_jvm_options.value();
+
+                let req = UpdateServerConfigRequest {
+                    id: config.id,
+                    name: Some(name).filter(|name| *name != config.name),
+                    version: Some(version).filter(|version| *version != config.version),
+                    flavor: Some(flavor).filter(|flavor| *flavor != config.flavor),
+                    world_uri: if let UploadUriStatus::Finish { uri } = &self.upload_world {
+                        Some(uri.clone())
+                    } else {
+                        None
+                    },
+                    resource_uri: if let UploadUriStatus::Finish { uri } = &self.upload_resource {
+                        Some(uri.clone())
+                    } else {
+                        None
+                    },
+                    motd: Some(motd).filter(|motd| *motd != config.motd),
+                    restart_policy: Some(restart_policy)
+                        .filter(|restart_policy| *restart_policy != config.restart_policy),
+                    restart_max_retries: Some(restart_max_retries).filter(|restart_max_retries| {
+                        *restart_max_retries != config.restart_max_retries
+                    }),
+                    properties: Some(properties)
+                        .filter(|properties| *properties != config.properties),
+                    jvm_options: Some(jvm_options)
+                        .filter(|jvm_options| *jvm_options != config.jvm_options),
+                };
+                info!("update server config: {req:?}");
+
+                Self::update_server_config(req, ctx.link().clone());
+                return true;
+            }
+            McServerManagePageMsg::FinishUpdateServerConfig => {
+                self.submit_status = SubmitStatus::Idle;
+                self.editing_server_config = None;
+
+                if let Some(modal) = self.edit_server_config_modal.borrow().as_ref() {
+                    modal.hide();
+                }
+
+                Self::load_server_configs(self.server_config_page, ctx.link().clone());
+                return true;
+            }
+            McServerManagePageMsg::ErrorUpdateServerConfig { err } => {
+                self.submit_status = SubmitStatus::Error { err };
+                return true;
+            }
+            McServerManagePageMsg::SelectConsoleServer { id } => {
+                if self.console_server_id == Some(id) {
+                    return false;
+                }
+
+                if let Some(event_source) = self.console_event_source.take() {
+                    event_source.close();
+                }
+                self.console_server_id = Some(id);
+                ctx.link()
+                    .send_message(McServerManagePageMsg::ConnectConsole);
+                return true;
+            }
+            McServerManagePageMsg::ConnectConsole => {
+                let Some(id) = self.console_server_id else {
+                    return false;
+                };
+
+                // 服务端会先推送历史日志，因此重连时清空已有内容
+                self.console_lines.clear();
+                match Self::connect_console(id, ctx.link().clone()) {
+                    Ok(event_source) => self.console_event_source = Some(event_source),
+                    Err(err) => {
+                        error!("{err}");
+                        ctx.link()
+                            .send_message(McServerManagePageMsg::DisconnectConsole { id });
+                    }
+                }
+                return true;
+            }
+            McServerManagePageMsg::ReceiveConsoleLine { id, line } => {
+                // 忽略切换服务器前旧连接的数据
+                if self.console_server_id != Some(id) {
+                    return false;
+                }
+
+                if self.console_lines.len() >= MAX_CONSOLE_LINES {
+                    self.console_lines.pop_front();
+                }
+                self.console_lines.push_back(line.line);
+                return true;
+            }
+            McServerManagePageMsg::DisconnectConsole { id } => {
+                if self.console_server_id != Some(id) {
+                    return false;
+                }
+
+                if let Some(event_source) = self.console_event_source.take() {
+                    event_source.close();
+                }
+
+                let link = ctx.link().clone();
+                Timeout::new(3000, move || {
+                    link.send_message(McServerManagePageMsg::ConnectConsole);
+                })
+                .forget();
+                return false;
+            }
+            McServerManagePageMsg::SubmitServerCommand => {
+                let Some(id) = self.console_server_id else {
+                    return false;
+                };
+
+                let input = self
+                    .console_command
+                    .cast::<HtmlInputElement>()
+                    .expect("command should be a input element");
+                let command = input.value();
+                if command.trim().is_empty() {
+                    return false;
+                }
+
+                input.set_value("");
+                Self::send_server_command(id, command);
+                return false;
+            }
+        }
+    }
+
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        // 保持控制台滚动到最新一行
+        if let Some(output) = self.console_output.cast::<Element>() {
+            output.set_scroll_top(output.scroll_height());
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let show_create_server_config_dialog = {
-            let dialog = self.create_server_config_dialog.clone();
-            let modal = self.create_server_config_modal.clone();
+            let link = ctx.link().clone();
             Callback::from(move |_e: MouseEvent| {
-                if modal.borrow().is_none() {
-                    let element = dialog
-                        .cast()
-                        .expect("Cannot cast dialog node_ref to Element");
-                    *modal.borrow_mut() = Some(Modal::new_with_element(&element))
-                }
-                modal
-                    .borrow()
-                    .as_ref()
-                    .expect("modal should be initialized before")
-                    .show();
+                link.send_message(McServerManagePageMsg::ShowCreateServerConfig);
+            })
+        };
+
+        let on_edit_config = {
+            let link = ctx.link().clone();
+            Callback::from(move |id: u64| {
+                link.send_message(McServerManagePageMsg::ShowEditServerConfig { id });
             })
         };
 
@@ -588,13 +1320,40 @@ impl Component for McServerManagePage {
             })
         };
 
+        let on_submit_edit_form = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: SubmitEvent| {
+                e.prevent_default();
+                link.send_message(McServerManagePageMsg::SubmitUpdateServerConfig);
+            })
+        };
+
+        let on_submit_command = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: SubmitEvent| {
+                e.prevent_default();
+                link.send_message(McServerManagePageMsg::SubmitServerCommand);
+            })
+        };
+
         let on_start_config = Callback::from(|id: u64| {
             Self::start_server_config(id);
         });
 
-        let on_stop_config = Callback::from(|_e: MouseEvent| {
-            Self::stop_server_config();
-        });
+        let on_select_console_server = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: Event| {
+                let select = e
+                    .target()
+                    .expect("event target should be exists")
+                    .dyn_into::<HtmlSelectElement>()
+                    .expect("this noderef should be select element");
+                let Ok(id) = select.value().parse() else {
+                    return;
+                };
+                link.send_message(McServerManagePageMsg::SelectConsoleServer { id });
+            })
+        };
 
         let server_config_prev_page = {
             let page = self.server_config_page;
@@ -652,69 +1411,50 @@ impl Component for McServerManagePage {
                 <div class="container-sm">
                     <h3>{"MC 服务器管理"}</h3>
 
-                    <div class="card">
+                    {
+                        if self.running_servers.is_empty() {
+                            html! {
+                                <div class="card">
+                                    <div class="card-body">
+                                        <span class="card-text">{"当前没有运行中的服务器"}</span>
+                                    </div>
+                                </div>
+                            }
+                        } else {
+                            self.running_servers.iter().map(|server| self.view_running_server(server)).collect::<Html>()
+                        }
+                    }
+
+                    <div class="card" style="margin-top: 24px;">
                         <div class="card-header">
-                            <span class="card-text">
-                                {"当前状态："}
+                            <span class="card-text">{"控制台"}</span>
+                            <select class="form-select form-select-sm float-end" style="width: auto;" onchange={on_select_console_server}>
                                 {
-                                    if self.current_server_config_error {
+                                    self.running_servers.iter().map(|server| {
+                                        let id = server.config.id;
                                         html! {
-                                            <span class="badge bg-danger">{"已停止"}</span>
+                                            <option value={id.to_string()} selected={self.console_server_id == Some(id)}>
+                                                {format!("{} ({})", server.config.name, id)}
+                                            </option>
                                         }
-                                    } else {
-                                        match self.current_server_config_status {
-                                            RunningServerStage::Init | RunningServerStage::Stopped => html! {
-                                                <span class="badge bg-info text-dark">{"已停止"}</span>
-                                            },
-                                            RunningServerStage::PullingServer | RunningServerStage::PullingWorld | RunningServerStage::InitializingFile | RunningServerStage::Starting => html! {
-                                                <span class="badge bg-primary">{"启动中"}</span>
-                                            },
-                                            RunningServerStage::Running => html! {
-                                                <span class="badge bg-success">{"运行中"}</span>
-                                            },
-                                            RunningServerStage::Stopping => html! {
-                                                <span class="badge bg-info text-dark">{"停止中"}</span>
-                                            }
-                                        }
-                                    }
+                                    }).collect::<Html>()
                                 }
-
-                            </span>
-                            <button type="button" class="btn btn-sm btn-outline-danger float-end" onclick={on_stop_config}>{"停止服务器"}</button>
+                            </select>
                         </div>
-                        <div class="card-body" style="overflow-x: auto; padding: 2rem;">
-                            <div class="row g-0" style="width: 2000px;">
+                        <div class="card-body">
+                            <pre ref={self.console_output.clone()} style="height: 400px; overflow-y: auto; margin-bottom: 0; white-space: pre-wrap;">
                                 {
-                                    [
-                                        (RunningServerStage::PullingServer, "下载服务端程序", true),
-                                        (RunningServerStage::PullingWorld, "下载存档", true),
-                                        (RunningServerStage::InitializingFile, "生成配置文件", true),
-                                        (RunningServerStage::Starting, "启动服务器", true),
-                                        (RunningServerStage::Running, "等待游戏结束", true),
-                                        (RunningServerStage::Stopping, "停止服务器", false)
-                                    ].into_iter().map(|(stage, title, has_next)| {
-                                        let stage = self.current_server_config.get(&stage);
-                                        let icon = if let Some(stage) = stage {
-                                            if stage.in_error {
-                                                ProgressIcon::Error
-                                            } else if stage.finish_time.is_some() {
-                                                ProgressIcon::Pass
-                                            } else {
-                                                ProgressIcon::Running
-                                            }
-                                        } else {
-                                            ProgressIcon::Waiting
-                                        };
-
-                                        let start_time = stage.as_ref().map(|stage| stage.enter_time);
-                                        let end_time = stage.as_ref().and_then(|stage| stage.finish_time);
-
-                                        html! {
-                                            <ProgressNode title={title} has_next={has_next} icon={icon} start_time={start_time} end_time={end_time} />
-                                        }
+                                    self.console_lines.iter().map(|line| html! {
+                                        <>{line}{"\n"}</>
                                     }).collect::<Html>()
                                 }
-                            </div>
+                            </pre>
+                        </div>
+                        <div class="card-footer">
+                            <form class="input-group" onsubmit={on_submit_command}>
+                                <input type="text" class="form-control" placeholder="输入命令，如 say hello" maxlength={256} ref={self.console_command.clone()} />
+                                <button type="submit" class="btn btn-outline-primary">{"发送"}</button>
+                            </form>
                         </div>
                     </div>
 
@@ -740,8 +1480,10 @@ impl Component for McServerManagePage {
                                             id={server_config.id}
                                             name={server_config.name.clone()}
                                             version={server_config.version.clone()}
+                                            flavor={server_config.flavor}
                                             motd={server_config.motd.clone()}
-                                            onstart={on_start_config.clone()} />
+                                            onstart={on_start_config.clone()}
+                                            onedit={on_edit_config.clone()} />
                                     }).collect::<Html>()
                                 } else {
                                     (0..3).into_iter().map(|_| html!{
@@ -819,7 +1561,7 @@ impl Component for McServerManagePage {
                                     <h5 class="modal-title">{"新增服务器"}</h5>
                                     <button type="button" class="btn-close" data-bs-dismiss="modal" aria-label="Close"></button>
                                 </div>
-                                <div class="modal-body">
+                                <div class="modal-body" key={format!("create-{}", self.dialog_serial)}>
                                     <div class="row mb-3">
                                         {
                                             if let SubmitStatus::Error{ err } = &self.submit_status {
@@ -864,6 +1606,19 @@ impl Component for McServerManagePage {
                                         </div>
                                     </div>
 
+                                    <div class="row mb-3">
+                                        <label for="flavor" class="col-sm-4 col-form-label">
+                                            {"服务端类型"}
+                                        </label>
+                                        <div class="col-sm-8">
+                                            <select class="form-control form-select" id="flavor" ref={self.create_server_config_flavor.clone()}>
+                                                <option value="vanilla" selected={true}>{"原版"}</option>
+                                                <option value="paper">{"Paper"}</option>
+                                                <option value="fabric">{"Fabric"}</option>
+                                            </select>
+                                        </div>
+                                    </div>
+
                                     <div class="row mb-3">
                                         <label for="world" class="col-sm-4 col-form-label">
                                             {"预设存档"}
@@ -881,7 +1636,7 @@ impl Component for McServerManagePage {
                                         </label>
                                         <div class="col-sm-8">
                                             <input type="file" class="form-control" id="world" accept=".zip"
-                                                onchange={world_file_select} />
+                                                onchange={world_file_select.clone()} />
                                         </div>
                                     </div>
 
@@ -916,7 +1671,7 @@ impl Component for McServerManagePage {
                                         </label>
                                         <div class="col-sm-8">
                                             <input type="file" class="form-control" id="resource" accept=".zip"
-                                                onchange={resource_file_select} />
+                                                onchange={resource_file_select.clone()} />
                                         </div>
                                     </div>
 
@@ -949,6 +1704,32 @@ impl Component for McServerManagePage {
                                             </p>
                                         </div>
                                     </div>
+
+                                    <div class="row mb-3">
+                                        <label for="restart_policy" class="col-sm-4 col-form-label">
+                                            {"崩溃重启"}
+                                        </label>
+                                        <div class="col-sm-8">
+                                            <select class="form-control form-select" id="restart_policy" ref={self.create_server_config_restart_policy.clone()}>
+                                                <option value="never" selected={true}>{"不重启"}</option>
+                                                <option value="on_failure">{"异常退出时重启"}</option>
+                                                <option value="always">{"总是重启"}</option>
+                                            </select>
+                                        </div>
+                                    </div>
+
+                                    <div class="row mb-3">
+                                        <label for="restart_max_retries" class="col-sm-4 col-form-label">
+                                            {"最大重试次数"}
+                                        </label>
+                                        <div class="col-sm-8">
+                                            <input type="number" class="form-control" id="restart_max_retries" min="0" max="100" value="3" ref={self.create_server_config_restart_max_retries.clone()} />
+                                        </div>
+                                    </div>
+
+                                    { self.create_server_config_properties.view("create", &ServerProperties::default()) }
+
+                                    { self.create_server_config_jvm_options.view("create", &JvmOptions::default()) }
                                 </div>
                                 <div class="modal-footer">
                                     <button type="button" class="btn btn-secondary" data-bs-dismiss="modal">{"取消"}</button>
@@ -971,6 +1752,42 @@ impl Component for McServerManagePage {
                             </form>
                         </div>
                     </div>
+
+                    <div class="modal fade" ref={self.edit_server_config_dialog.clone()}>
+                        <div class="modal-dialog modal-dialog-centered modal-dialog-scrollable">
+                            <form class="modal-content" onsubmit={on_submit_edit_form}>
+                                <div class="modal-header">
+                                    <h5 class="modal-title">{"编辑服务器"}</h5>
+                                    <button type="button" class="btn-close" data-bs-dismiss="modal" aria-label="Close"></button>
+                                </div>
+                                {
+                                    if let Some(config) = &self.editing_server_config {
+                                        self.view_edit_server_config_form(config, world_file_select, resource_file_select)
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                                <div class="modal-footer">
+                                    <button type="button" class="btn btn-secondary" data-bs-dismiss="modal">{"取消"}</button>
+                                    <button type="submit" class="btn btn-primary"
+                                        disabled={!self.upload_world.is_can_submit() || !self.upload_resource.is_can_submit()}>
+                                        {"保存"}
+                                        {
+                                            if matches!(self.submit_status, SubmitStatus::Submitting) {
+                                                html! {
+                                                    <div class="spinner-border text-primary" role="status">
+                                                        <span class="visually-hidden">{"Submitting"}</span>
+                                                    </div>
+                                                }
+                                            } else {
+                                                html!{}
+                                            }
+                                        }
+                                    </button>
+                                </div>
+                            </form>
+                        </div>
+                    </div>
                 </div>
             </>
         }
@@ -984,6 +1801,63 @@ pub struct ProgressNodeProps {
     icon: ProgressIcon,
     start_time: Option<i64>,
     end_time: Option<i64>,
+    progress: Option<StageProgress>,
+    // 完整的错误信息包含进程最后的输出，只显示第一行
+    error: Option<AttrValue>,
+}
+
+// 下载阶段的进度，解压开始后显示解压的文件数
+#[derive(PartialEq, Eq)]
+struct StageProgress {
+    text: AttrValue,
+    percent: Option<u64>,
+}
+
+fn stage_progress(stage: &RunningServerStageInfo) -> Option<StageProgress> {
+    if let (Some(extracted), Some(total)) = (stage.entries_extracted, stage.entries_total) {
+        if extracted > 0 || stage.finish_time.is_some() {
+            return Some(StageProgress {
+                text: AttrValue::from(format!("已解压 {extracted}/{total} 个文件")),
+                percent: (total > 0).then(|| extracted * 100 / total),
+            });
+        }
+    }
+
+    let done = stage.bytes_done?;
+    Some(match stage.bytes_total {
+        Some(total) => StageProgress {
+            text: AttrValue::from(format!(
+                "已下载 {}/{}",
+                format_bytes(done),
+                format_bytes(total)
+            )),
+            percent: (total > 0).then(|| done.min(total) * 100 / total),
+        },
+        None => StageProgress {
+            text: AttrValue::from(format!("已下载 {}", format_bytes(done))),
+            percent: None,
+        },
+    })
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in UNITS.into_iter().skip(1) {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+
+    format!("{value:.1} {unit}")
 }
 
 #[derive(PartialEq, Eq)]
@@ -1089,6 +1963,45 @@ pub fn ProgressNode(props: &ProgressNodeProps) -> Html {
                             style="text-overflow: ellipsis; white-space: nowrap; overflow: hidden;">
                             <small style="text-muted">{runtime_text.clone()}</small>
                         </p>
+                        {
+                            if let Some(error) = &props.error {
+                                let summary = error.lines().next().unwrap_or_default().to_owned();
+                                html! {
+                                    <p class="card-text text-danger" title={error.clone()}
+                                        style="text-overflow: ellipsis; white-space: nowrap; overflow: hidden; margin-bottom: 4px;">
+                                        <small>{summary}</small>
+                                    </p>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        {
+                            if let Some(progress) = &props.progress {
+                                html! {
+                                    <>
+                                        <p class="card-text" title={progress.text.clone()}
+                                            style="text-overflow: ellipsis; white-space: nowrap; overflow: hidden; margin-bottom: 4px;">
+                                            <small style="text-muted">{progress.text.clone()}</small>
+                                        </p>
+                                        {
+                                            if let Some(percent) = progress.percent {
+                                                html! {
+                                                    <div class="progress" style="height: 4px;">
+                                                        <div class="progress-bar" role="progressbar" style={format!("width: {percent}%;")}
+                                                            aria-valuenow={percent.to_string()} aria-valuemin="0" aria-valuemax="100"></div>
+                                                    </div>
+                                                }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
+                                    </>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
                     </div>
                 </div>
             </div>
@@ -1103,13 +2016,263 @@ pub fn ProgressNode(props: &ProgressNodeProps) -> Html {
     }
 }
 
+// 新增和编辑服务器共用的 server.properties 表单
+#[derive(Default)]
+struct ServerPropertiesForm {
+    gamemode: NodeRef,
+    difficulty: NodeRef,
+    max_players: NodeRef,
+    view_distance: NodeRef,
+    level_seed: NodeRef,
+    pvp: NodeRef,
+    white_list: NodeRef,
+    online_mode: NodeRef,
+    enable_command_block: NodeRef,
+    require_resource_pack: NodeRef,
+}
+
+impl ServerPropertiesForm {
+    // 留空的选项不提交，使用服务器默认值
+    fn value(&self) -> ServerProperties {
+        let select_value = |node: &NodeRef| {
+            node.cast::<HtmlSelectElement>()
+                .expect("property should be a select element")
+                .value()
+        };
+        let input_value = |node: &NodeRef| {
+            node.cast::<HtmlInputElement>()
+                .expect("property should be a input element")
+                .value()
+        };
+        let bool_value = |node: &NodeRef| match select_value(node).as_str() {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        };
+
+        ServerProperties {
+            gamemode: match select_value(&self.gamemode).as_str() {
+                "survival" => Some(GameMode::Survival),
+                "creative" => Some(GameMode::Creative),
+                "adventure" => Some(GameMode::Adventure),
+                "spectator" => Some(GameMode::Spectator),
+                _ => None,
+            },
+            difficulty: match select_value(&self.difficulty).as_str() {
+                "peaceful" => Some(Difficulty::Peaceful),
+                "easy" => Some(Difficulty::Easy),
+                "normal" => Some(Difficulty::Normal),
+                "hard" => Some(Difficulty::Hard),
+                _ => None,
+            },
+            max_players: input_value(&self.max_players).parse().ok(),
+            pvp: bool_value(&self.pvp),
+            level_seed: Some(input_value(&self.level_seed)).filter(|seed| !seed.is_empty()),
+            white_list: bool_value(&self.white_list),
+            online_mode: bool_value(&self.online_mode),
+            view_distance: input_value(&self.view_distance).parse().ok(),
+            enable_command_block: bool_value(&self.enable_command_block),
+            require_resource_pack: bool_value(&self.require_resource_pack),
+        }
+    }
+
+    fn view(&self, id_prefix: &str, properties: &ServerProperties) -> Html {
+        let gamemodes = [
+            (GameMode::Survival, "survival", "生存"),
+            (GameMode::Creative, "creative", "创造"),
+            (GameMode::Adventure, "adventure", "冒险"),
+            (GameMode::Spectator, "spectator", "旁观"),
+        ];
+        let difficulties = [
+            (Difficulty::Peaceful, "peaceful", "和平"),
+            (Difficulty::Easy, "easy", "简单"),
+            (Difficulty::Normal, "normal", "普通"),
+            (Difficulty::Hard, "hard", "困难"),
+        ];
+        let bool_select = |id: &str, label: &str, node: &NodeRef, value: Option<bool>| {
+            let id = format!("{id_prefix}_{id}");
+            html! {
+                <div class="row mb-3">
+                    <label for={id.clone()} class="col-sm-4 col-form-label">
+                        {label}
+                    </label>
+                    <div class="col-sm-8">
+                        <select class="form-control form-select" id={id} ref={node.clone()}>
+                            <option value="" selected={value.is_none()}>{"默认"}</option>
+                            <option value="true" selected={value == Some(true)}>{"开启"}</option>
+                            <option value="false" selected={value == Some(false)}>{"关闭"}</option>
+                        </select>
+                    </div>
+                </div>
+            }
+        };
+
+        html! {
+            <>
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_gamemode")} class="col-sm-4 col-form-label">
+                        {"游戏模式"}
+                    </label>
+                    <div class="col-sm-8">
+                        <select class="form-control form-select" id={format!("{id_prefix}_gamemode")} ref={self.gamemode.clone()}>
+                            <option value="" selected={properties.gamemode.is_none()}>{"默认"}</option>
+                            {
+                                gamemodes.into_iter().map(|(gamemode, value, text)| html! {
+                                    <option value={value} selected={properties.gamemode == Some(gamemode)}>{text}</option>
+                                }).collect::<Html>()
+                            }
+                        </select>
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_difficulty")} class="col-sm-4 col-form-label">
+                        {"难度"}
+                    </label>
+                    <div class="col-sm-8">
+                        <select class="form-control form-select" id={format!("{id_prefix}_difficulty")} ref={self.difficulty.clone()}>
+                            <option value="" selected={properties.difficulty.is_none()}>{"默认"}</option>
+                            {
+                                difficulties.into_iter().map(|(difficulty, value, text)| html! {
+                                    <option value={value} selected={properties.difficulty == Some(difficulty)}>{text}</option>
+                                }).collect::<Html>()
+                            }
+                        </select>
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_max_players")} class="col-sm-4 col-form-label">
+                        {"最大玩家数"}
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="number" class="form-control" id={format!("{id_prefix}_max_players")} min="1" max="1000" placeholder="默认"
+                            value={properties.max_players.map(|v| v.to_string()).unwrap_or_default()} ref={self.max_players.clone()} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_view_distance")} class="col-sm-4 col-form-label">
+                        {"视距"}
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="number" class="form-control" id={format!("{id_prefix}_view_distance")} min="3" max="32" placeholder="默认"
+                            value={properties.view_distance.map(|v| v.to_string()).unwrap_or_default()} ref={self.view_distance.clone()} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_level_seed")} class="col-sm-4 col-form-label">
+                        {"世界种子"}
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="text" class="form-control" id={format!("{id_prefix}_level_seed")} maxlength="64" placeholder="随机"
+                            value={properties.level_seed.clone().unwrap_or_default()} ref={self.level_seed.clone()} />
+                    </div>
+                </div>
+
+                { bool_select("pvp", "PVP", &self.pvp, properties.pvp) }
+                { bool_select("white_list", "白名单", &self.white_list, properties.white_list) }
+                { bool_select("online_mode", "正版验证", &self.online_mode, properties.online_mode) }
+                { bool_select("enable_command_block", "命令方块", &self.enable_command_block, properties.enable_command_block) }
+                { bool_select("require_resource_pack", "强制资源包", &self.require_resource_pack, properties.require_resource_pack) }
+            </>
+        }
+    }
+}
+
+// 新增和编辑服务器共用的启动参数表单
+#[derive(Default)]
+struct JvmOptionsForm {
+    min_memory: NodeRef,
+    max_memory: NodeRef,
+    extra_args: NodeRef,
+    aikar_flags: NodeRef,
+}
+
+impl JvmOptionsForm {
+    fn value(&self) -> JvmOptions {
+        let input_value = |node: &NodeRef| {
+            node.cast::<HtmlInputElement>()
+                .expect("jvm option should be a input element")
+                .value()
+        };
+
+        // 每行一个参数
+        let extra_args = self
+            .extra_args
+            .cast::<HtmlTextAreaElement>()
+            .expect("extra args should be a text area element")
+            .value()
+            .lines()
+            .map(str::trim)
+            .filter(|arg| !arg.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        JvmOptions {
+            min_memory_mb: input_value(&self.min_memory).parse().ok(),
+            max_memory_mb: input_value(&self.max_memory).parse().ok(),
+            extra_args,
+            aikar_flags: self
+                .aikar_flags
+                .cast::<HtmlInputElement>()
+                .expect("aikar flags should be a input element")
+                .checked(),
+        }
+    }
+
+    fn view(&self, id_prefix: &str, jvm_options: &JvmOptions) -> Html {
+        html! {
+            <>
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_min_memory")} class="col-sm-4 col-form-label">
+                        {"最小内存(MB)"}
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="number" class="form-control" id={format!("{id_prefix}_min_memory")} min="1" placeholder="默认"
+                            value={jvm_options.min_memory_mb.map(|v| v.to_string()).unwrap_or_default()} ref={self.min_memory.clone()} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_max_memory")} class="col-sm-4 col-form-label">
+                        {"最大内存(MB)"}
+                    </label>
+                    <div class="col-sm-8">
+                        <input type="number" class="form-control" id={format!("{id_prefix}_max_memory")} min="1" placeholder="默认"
+                            value={jvm_options.max_memory_mb.map(|v| v.to_string()).unwrap_or_default()} ref={self.max_memory.clone()} />
+                    </div>
+                </div>
+
+                <div class="row mb-3">
+                    <label for={format!("{id_prefix}_extra_args")} class="col-sm-4 col-form-label">
+                        {"JVM参数"}
+                    </label>
+                    <div class="col-sm-8">
+                        <textarea class="form-control" id={format!("{id_prefix}_extra_args")} placeholder="每行一个参数"
+                            value={jvm_options.extra_args.join("\n")} ref={self.extra_args.clone()} />
+                        <div class="form-check">
+                            <input type="checkbox" class="form-check-input" id={format!("{id_prefix}_aikar_flags")}
+                                checked={jvm_options.aikar_flags} ref={self.aikar_flags.clone()} />
+                            <label for={format!("{id_prefix}_aikar_flags")}>{"使用 Aikar 推荐参数"}</label>
+                        </div>
+                    </div>
+                </div>
+            </>
+        }
+    }
+}
+
 #[derive(PartialEq, Properties)]
 pub struct ServerConfigProps {
     id: u64,
     name: String,
     version: String,
+    flavor: ServerFlavor,
     motd: String,
     onstart: Callback<u64>,
+    onedit: Callback<u64>,
 }
 
 #[function_component]
@@ -1118,25 +2281,43 @@ pub fn ServerConfigNode(props: &ServerConfigProps) -> Html {
         id,
         name,
         version,
+        flavor,
         motd,
         onstart,
+        onedit,
     } = props;
 
+    let flavor = match flavor {
+        ServerFlavor::Vanilla => "原版",
+        ServerFlavor::Paper => "Paper",
+        ServerFlavor::Fabric => "Fabric",
+    };
+
     let on_start_click = {
         let id = *id;
         let onstart = onstart.clone();
         Callback::from(move |_: MouseEvent| onstart.emit(id))
     };
 
+    let on_edit_click = {
+        let id = *id;
+        let onedit = onedit.clone();
+        Callback::from(move |_: MouseEvent| onedit.emit(id))
+    };
+
     html! {
         <tr>
             <th scope="row">{id}</th>
             <td>{name}</td>
-            <td>{version}</td>
+            <td>
+                {version}
+                <span class="badge text-bg-secondary ms-1">{flavor}</span>
+            </td>
             <td>{motd}</td>
             <td>
                 <div class="btn-group" role="group">
                     <button type="button" class="btn btn-sm btn-outline-success" onclick={on_start_click}>{"启动"}</button>
+                    <button type="button" class="btn btn-sm btn-outline-primary" onclick={on_edit_click}>{"编辑"}</button>
                 </div>
             </td>
         </tr>